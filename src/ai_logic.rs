@@ -1,47 +1,320 @@
-use crate::{board, constants};
+use std::fmt;
+use std::time::Duration;
 
-mod monte_carlo_tree_search;
+use crate::{board, constants, seed};
+use crate::ai_logic::monte_carlo_tree_search::MctsSettings;
+use crate::ai_logic::personality::Personality;
+use crate::ai_logic::bots::Bot;
+use crate::ai_logic::transposition::TranspositionTable;
+use crate::seed::GameRng;
+
+pub mod monte_carlo_tree_search;
 mod minimax;
+mod endgame;
+mod move_ordering;
+pub mod transposition;
+pub mod probcut;
+pub mod mtdf;
+pub mod neural_network;
+pub mod training;
+pub mod strength;
+pub mod personality;
+pub mod bots;
+
+pub mod random_ai;
 
-mod random_ai;
+/// The names AiType::from_name understands, for help texts
+pub const ENGINE_NAMES: &str = "random, greedy, mobility, corners, minimax, mcts, level<1-15>, epsilon<0-1>:<engine>";
 
-pub enum AI_Type{
+/// The engines the game and the tournament runner can play with
+#[derive(Clone, PartialEq, Debug)]
+pub enum AiType{
     Random,
     Minimax,
-    MCTS
+    Mcts,
+    // A strength level from 1 to strength::LEVELS.len()
+    Level(usize),
+    Bot(Bot),
+    // A random move with the given probability, the move of the inner engine otherwise
+    EpsilonGreedy(f64, Box<AiType>)
+}
+
+impl AiType {
+    /// Returns the engine with the given name, as listed in ENGINE_NAMES and printed by Display
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let engine = AiType::from_name("epsilon0.1:greedy");
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        match name.as_str() {
+            "random" => Some(AiType::Random),
+            "greedy" => Some(AiType::Bot(Bot::Greedy)),
+            "mobility" => Some(AiType::Bot(Bot::MinMobility)),
+            "corners" => Some(AiType::Bot(Bot::CornerEdge)),
+            "minimax" => Some(AiType::Minimax),
+            "mcts" => Some(AiType::Mcts),
+            _ => {
+                if let Some(number) = name.strip_prefix("level") {
+                    return number.parse().ok().filter(|&number| strength::level(number).is_some()).map(AiType::Level);
+                }
+                let (epsilon, engine) = name.strip_prefix("epsilon")?.split_once(':')?;
+                let epsilon = epsilon.parse::<f64>().ok().filter(|epsilon| (0.0..=1.0).contains(epsilon))?;
+                Some(AiType::EpsilonGreedy(epsilon, Box::new(AiType::from_name(engine)?)))
+            }
+        }
+    }
+
+    /// Picks the engine's move, 0 if the player to move has no legal move
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The current game-state
+    ///  * 'time_budget' - How long the engine may think
+    ///  * 'options' - The engine settings
+    ///  * 'table' - The memory of the searching engines' transposition table, every search starts it empty
+    ///  * 'rng' - The random number generator every random choice of the engine is drawn from
+    pub fn choose_move(&self, board: &board::Board, time_budget: Duration, options: &EngineOptions, table: &mut TranspositionTable, rng: &mut GameRng) -> u64 {
+        match self {
+            AiType::Random => {
+                random_ai::get_random_move(board.get_possible_moves(), rng)
+            }
+            AiType::Minimax => {
+                 minimax::get_minimax_move(board.clone(), time_budget, options, table)
+            }
+            AiType::Mcts => {
+                monte_carlo_tree_search::get_mcts_move(board.clone(), time_budget, options, rng)
+            }
+            AiType::Level(number) => {
+                strength::get_level_move(board, strength::LEVELS[number - 1], time_budget, options, table, rng)
+            }
+            AiType::Bot(bot) => {
+                bot.get_move(board)
+            }
+            AiType::EpsilonGreedy(epsilon, engine) => {
+                bots::epsilon_greedy(board, *epsilon, rng, |rng| engine.choose_move(board, time_budget, options, table, rng))
+            }
+        }
+    }
+}
+
+impl fmt::Display for AiType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AiType::Random => write!(f, "random"),
+            AiType::Minimax => write!(f, "minimax"),
+            AiType::Mcts => write!(f, "mcts"),
+            AiType::Level(number) => write!(f, "level{}", number),
+            AiType::Bot(Bot::Greedy) => write!(f, "greedy"),
+            AiType::Bot(Bot::MinMobility) => write!(f, "mobility"),
+            AiType::Bot(Bot::CornerEdge) => write!(f, "corners"),
+            AiType::EpsilonGreedy(epsilon, engine) => write!(f, "epsilon{}:{}", epsilon, engine),
+        }
+    }
+}
+
+/// The algorithm the minimax engine drives its search with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchAlgorithm {
+    Pvs,
+    Mtdf
+}
+
+/// How the engines score positions the search does not look past
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Evaluator {
+    // The hand-written evaluation in minimax
+    Heuristic,
+    // The network loaded with neural_network::load, or the heuristic if none was loaded
+    Neural
+}
+
+/// Settings of the minimax engine, chosen on the command line
+#[derive(Clone, Copy, Debug)]
+pub struct EngineOptions {
+    pub algorithm: SearchAlgorithm,
+    // Multi-ProbCut selectivity level, 0 turns it off
+    pub selectivity: usize,
+    pub evaluator: Evaluator,
+    // Weights of the heuristic evaluation the minimax searches use
+    pub personality: Personality,
+    // Settings of the tree search used by Hard
+    pub mcts: MctsSettings
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: SearchAlgorithm::Pvs,
+            selectivity: constants::DEFAULT_SELECTIVITY,
+            evaluator: Evaluator::Heuristic,
+            personality: Personality::Balanced,
+            mcts: MctsSettings::default(),
+        }
+    }
+}
+
+/// A result the endgame solver has proven for the player to move, with the final disc margin
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Win(i32),
+    Loss(i32),
+    Draw
+}
+
+impl Verdict {
+    /// Converts a final disc margin into a verdict
+    pub fn from_margin(margin: i32) -> Self {
+        match margin {
+            m if m > 0 => Verdict::Win(m),
+            m if m < 0 => Verdict::Loss(-m),
+            _ => Verdict::Draw,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Win(margin) => write!(f, "win by {}", margin),
+            Verdict::Loss(margin) => write!(f, "loss by {}", margin),
+            Verdict::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+/// The engine's opinion of a single legal move
+pub struct MoveAnalysis {
+    pub chosen_move: u64,
+    pub score: i32,
+    pub principal_variation: Vec<u64>,
+    pub verdict: Option<Verdict>
 }
 
-pub struct AI_Logic_Handler
+impl MoveAnalysis {
+    // Proven wins rank above every heuristic score and proven losses below them
+    fn rank_key(&self) -> (i32, i32) {
+        match self.verdict {
+            Some(Verdict::Win(margin)) => (2, margin),
+            Some(Verdict::Loss(margin)) => (0, -margin),
+            Some(Verdict::Draw) => (1, 0),
+            None => (1, self.score),
+        }
+    }
+}
+
+pub struct AiLogicHandler
 {
-    AI: AI_Type,
-    Color: constants::Color
+    ai: AiType,
+    color: constants::Color,
+    options: EngineOptions,
+    rng: GameRng,
+    // Allocated once for the whole game, each search starts it empty so only the memory is reused
+    table: TranspositionTable
 }
 
-impl AI_Logic_Handler {
-    pub fn new(Color: constants::Color, ai_difficulty: constants::Difficulty) -> Self {
-        let AI= match ai_difficulty {
-            constants::Difficulty::Easy => AI_Type::Random,
-            constants::Difficulty::Normal => AI_Type::Minimax,
-            constants::Difficulty::Hard => AI_Type::MCTS,
-            _ => AI_Type::Minimax
+impl AiLogicHandler {
+    /// Creates the AI for the given difficulty. Every random choice it makes comes from the seed
+    pub fn new(color: constants::Color, ai_difficulty: constants::Difficulty, options: EngineOptions, seed: u64) -> Self {
+        let ai= match ai_difficulty {
+            constants::Difficulty::Easy => AiType::Random,
+            constants::Difficulty::Normal => AiType::Minimax,
+            constants::Difficulty::Hard => AiType::Mcts,
+            constants::Difficulty::Level(number) | constants::Difficulty::Adaptive(number) => match strength::level(number) {
+                Some(_) => AiType::Level(number),
+                None => AiType::Minimax,
+            },
+            constants::Difficulty::Engine(engine) => engine,
+            _ => AiType::Minimax
         };
-        Self {AI, Color}
+        Self {ai, color, options, rng: seed::rng(seed), table: TranspositionTable::new()}
     }
 
-    pub fn get_ai_move(&self, &possible_moves: &u64, board: board::Board) -> u64
+    /// Picks the AI's move for the given board.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'possible_moves' - The bitboard containing positions of all legal moves
+    ///  * 'board' - The current game-state
+    ///  * 'time_budget' - How long the AI may think, as allocated by its clock
+    pub fn get_ai_move(&mut self, &possible_moves: &u64, board: board::Board, time_budget: Duration) -> u64
     {
-        match self.AI {
-            AI_Type::Random => {
-                random_ai::get_random_move(possible_moves)
-            }
-            AI_Type::Minimax => {
-                 minimax::get_minimax_move(board, 30)
-            }
-            AI_Type::MCTS => {
-                // TODO Implement MCTS Algorithm
-                0
+        println!("{} is thinking for up to {:.1}s", self.color, time_budget.as_secs_f64());
+        match self.ai {
+            AiType::Random => random_ai::get_random_move(possible_moves, &mut self.rng),
+            _ => self.ai.choose_move(&board, time_budget, &self.options, &mut self.table, &mut self.rng),
+        }
+    }
 
-            }
+    /// Returns the engine the AI plays with
+    pub fn engine(&self) -> &AiType {
+        &self.ai
+    }
+
+    /// Returns the strength level the AI plays at, if it plays at one
+    pub fn level(&self) -> Option<usize> {
+        match self.ai {
+            AiType::Level(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    /// Moves the AI to another strength level, numbers outside the levels are ignored
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'number' - The level from 1 to strength::LEVELS.len()
+    pub fn set_level(&mut self, number: usize) {
+        if strength::level(number).is_some() {
+            self.ai = AiType::Level(number);
+        }
+    }
+
+    /// Returns how many evaluation points a move lost against the engine's best move, from a quick search
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position the move was played in
+    ///  * 'played_move' - The move that was played
+    pub fn move_loss(&mut self, board: &board::Board, played_move: u64) -> i32 {
+        strength::move_loss(board, played_move, &self.options, &mut self.table)
+    }
+
+    /// Runs the minimax analysis on the board and returns every legal move ranked by score. None of
+    /// the other engines ranks moves, so hints and reviews come from minimax whichever engine plays.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position to analyze
+    ///  * 'time_budget' - How long the analysis may run
+    pub fn analyze(&mut self, board: board::Board, time_budget: Duration) -> Vec<MoveAnalysis> {
+        minimax::analyze_moves(board, time_budget, &self.options, &mut self.table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engine_names_round_trip() {
+        let engines = [
+            AiType::Random,
+            AiType::Minimax,
+            AiType::Mcts,
+            AiType::Level(7),
+            AiType::Bot(Bot::Greedy),
+            AiType::Bot(Bot::MinMobility),
+            AiType::Bot(Bot::CornerEdge),
+            AiType::EpsilonGreedy(0.25, Box::new(AiType::EpsilonGreedy(0.5, Box::new(AiType::Bot(Bot::CornerEdge))))),
+        ];
+        for engine in engines {
+            assert_eq!(AiType::from_name(&engine.to_string()), Some(engine));
+        }
+        for name in ["level0", "level16", "epsilon2:greedy", "epsilon0.1", "epsilon0.1:unknown", "unknown"] {
+            assert_eq!(AiType::from_name(name), None);
         }
     }
 }
@@ -0,0 +1,174 @@
+//Imports
+use rand::Rng;
+
+//Modules
+use crate::bitboard::Bitboard;
+use crate::board;
+use crate::ai_logic::random_ai;
+
+/// A cheap fixed-rule player. In anti-Othello every rule about discs is turned around
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bot {
+    // The move that flips the most discs, the fewest in anti-Othello
+    Greedy,
+    // The move that leaves the opponent the fewest moves, flipping the most discs on a tie
+    MinMobility,
+    // Corners first, then edges, inner squares and the squares next to corners last. Anti-Othello
+    // plays them in the opposite order, as corners and edges are the discs that can not be lost
+    CornerEdge
+}
+
+impl Bot {
+    ///Picks the bot's move on a board of any size. Ties go to the lowest square, so the same position
+    /// always gets the same move. Returns 0 if the player to move has no legal move
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The current game-state
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let selected_move = Bot::Greedy.get_move(&board);
+    /// ```
+    pub fn get_move<B: Bitboard>(self, board: &board::Board<B>) -> B {
+        let moves = board.get_possible_moves();
+        let sign = board.variant.disc_sign();
+        // Flipped discs as the variant counts them, more is better
+        let gain = |square: B| flips(board, moves, square) as i32 * sign;
+        match self {
+            Bot::Greedy => squares(moves).max_by_key(|&square| (gain(square), std::cmp::Reverse(square.trailing_zeros()))),
+            Bot::MinMobility => {
+                let opponent = board.current_player.opponent();
+                squares(moves).min_by_key(|&square| {
+                    let mut after = board.clone();
+                    after.place_move(&moves, square);
+                    (after.count_mobility(opponent), std::cmp::Reverse(gain(square)))
+                })
+            }
+            Bot::CornerEdge => squares(moves).min_by_key(|&square| (square_class(square, board.size()) as i32 * sign, std::cmp::Reverse(gain(square)))),
+        }.unwrap_or(B::EMPTY)
+    }
+}
+
+///Plays a random legal move with probability epsilon and the engine's move otherwise. The engine
+/// is only asked for a move when it is played
+///
+///  # Arguments
+///
+///  * 'board' - The current game-state
+///  * 'epsilon' - The chance of a random move, from 0 to 1
+///  * 'rng' - The random number generator that decides, handed on to the engine
+///  * 'engine_move' - Picks the engine's move
+///
+///  # Examples
+///
+/// ```
+/// let selected_move = bots::epsilon_greedy(&board, 0.1, &mut rng, |_| Bot::Greedy.get_move(&board));
+/// ```
+pub fn epsilon_greedy<R: Rng>(board: &board::Board, epsilon: f64, rng: &mut R, engine_move: impl FnOnce(&mut R) -> u64) -> u64 {
+    match rng.gen_bool(epsilon.clamp(0.0, 1.0)) {
+        true => random_ai::get_random_move(board.get_possible_moves(), rng),
+        false => engine_move(rng),
+    }
+}
+
+// The single bit of every square in the set, lowest first
+fn squares<B: Bitboard>(set: B) -> impl Iterator<Item = B> {
+    let mut remaining = set;
+    std::iter::from_fn(move || {
+        let square = remaining.lowest_square();
+        remaining ^= square;
+        (square != B::EMPTY).then_some(square)
+    })
+}
+
+// Discs the move turns over
+fn flips<B: Bitboard>(board: &board::Board<B>, moves: B, square: B) -> u32 {
+    let mut after = board.clone();
+    after.place_move(&moves, square);
+    after.count_discs(board.current_player) - board.count_discs(board.current_player) - 1
+}
+
+// Lower classes are played first by the corner and edge bot. Corners come first, then edges,
+// inner squares, the squares next to a corner along an edge and the squares diagonally next to one
+fn square_class<B: Bitboard>(square: B, size: usize) -> u8 {
+    let index = square.trailing_zeros() as usize;
+    // How far the square is from the nearest edge, across the rows and along them
+    let distance = |line: usize| line.min(size - 1 - line);
+    let (row, col) = (distance(index / B::STRIDE), distance(index % B::STRIDE));
+    match (row.min(col), row.max(col)) {
+        (0, 0) => 0,
+        (1, 1) => 4,
+        (0, 1) => 3,
+        (0, _) => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The position after the lowest legal move was played the given number of times
+    fn position(plies: usize) -> board::Board {
+        let mut board = board::Board::new();
+        for _ in 0..plies {
+            let possible_moves = board.get_possible_moves();
+            board.place_move(&possible_moves, possible_moves & possible_moves.wrapping_neg());
+        }
+        board
+    }
+
+    #[test]
+    fn bots_play_legal_moves_and_pass_without_them() {
+        let mut board = board::Board::new();
+        let mut ply = 0;
+        while !board::Board::check_game_over(&board) {
+            let moves = board.get_possible_moves();
+            if moves == 0 {
+                for bot in [Bot::Greedy, Bot::MinMobility, Bot::CornerEdge] {
+                    assert_eq!(bot.get_move(&board), 0);
+                }
+                board.pass_turn();
+                continue;
+            }
+            let bot = [Bot::Greedy, Bot::MinMobility, Bot::CornerEdge][ply % 3];
+            let selected_move = bot.get_move(&board);
+            assert_ne!(moves & selected_move, 0);
+            assert_eq!(selected_move.count_ones(), 1);
+            board.place_move(&moves, selected_move);
+            ply += 1;
+        }
+        assert_eq!(random_ai::get_random_move(0u64, &mut crate::seed::rng(0)), 0);
+    }
+
+    #[test]
+    fn greedy_takes_the_most_discs() {
+        let board = position(5);
+        let moves = board.get_possible_moves();
+        let most = squares(moves).map(|square| flips(&board, moves, square)).max().unwrap();
+        assert_eq!(flips(&board, moves, Bot::Greedy.get_move(&board)), most);
+        assert_eq!(square_class(1u64 << 1, 8), 3);
+        assert_eq!(square_class(1u64 << 63, 8), 0);
+        assert_eq!(square_class(1u64 << 9, 8), 4);
+        // On a 6x6 board f6 is a corner and e5 is next to it
+        assert_eq!(square_class(1u64 << 45, 6), 0);
+        assert_eq!(square_class(1u64 << 36, 6), 4);
+    }
+
+    #[test]
+    fn bots_turn_their_disc_rules_around_in_anti_othello() {
+        for plies in 3..20 {
+            let board = position(plies).with_variant(crate::constants::Variant::Anti);
+            let moves = board.get_possible_moves();
+            if moves == 0 {
+                continue;
+            }
+            let fewest = squares(moves).map(|square| flips(&board, moves, square)).min().unwrap();
+            assert_eq!(flips(&board, moves, Bot::Greedy.get_move(&board)), fewest);
+            let worst_class = squares(moves).map(|square| square_class(square, 8)).max().unwrap();
+            assert_eq!(square_class(Bot::CornerEdge.get_move(&board), 8), worst_class);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//Imports
+use std::time::Instant;
+
+//Modules
+use crate::board;
+use crate::constants;
+
+/// Positions with this many empty squares or fewer can be solved exactly
+pub const SOLVER_EMPTIES: u32 = 12;
+// Stable discs are only counted when the window is high enough for the cutoff to be likely
+const STABILITY_CUTOFF_ALPHA: i32 = 0;
+// How many nodes are searched between checks of the clock
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+///Solves the position exactly and returns the final disc margin for the player to move.
+/// Returns None if the position could not be solved before end_time
+///
+///  # Arguments
+///
+///  * 'board' - The position to solve
+///  * 'end_time' - The time the solver has to give up at
+///
+///  # Examples
+///
+/// ```
+/// let end_time = Instant::now() + Duration::from_secs(2);
+/// if let Some(margin) = endgame::solve(&board, end_time) {
+///     println!("The player to move wins by {}", margin);
+/// }
+/// ```
+pub fn solve(board: &board::Board, end_time: Instant) -> Option<i32> {
+    let mut nodes: u64 = 0;
+    solve_node(board, -64, 64, false, &Limit { end_time: Some(end_time), nodes: u64::MAX }, &mut nodes)
+}
+
+///Solves the position exactly within a node budget instead of a time limit, so the answer never
+/// depends on the speed of the machine. Returns None if the position needs more nodes
+///
+///  # Arguments
+///
+///  * 'board' - The position to solve
+///  * 'node_limit' - The most nodes the solver may visit
+///
+///  # Examples
+///
+/// ```
+/// if let Some(margin) = endgame::solve_nodes(&board, 100_000) {
+///     println!("The player to move wins by {}", margin);
+/// }
+/// ```
+pub fn solve_nodes(board: &board::Board, node_limit: u64) -> Option<i32> {
+    let mut nodes: u64 = 0;
+    solve_node(board, -64, 64, false, &Limit { end_time: None, nodes: node_limit }, &mut nodes)
+}
+
+// When the solver has to give up
+struct Limit {
+    end_time: Option<Instant>,
+    nodes: u64
+}
+
+fn solve_node(board: &board::Board, mut alpha: i32, beta: i32, passed: bool, limit: &Limit, nodes: &mut u64) -> Option<i32> {
+    *nodes += 1;
+    if *nodes > limit.nodes {
+        return None;
+    }
+    if nodes.is_multiple_of(TIME_CHECK_INTERVAL) && limit.end_time.is_some_and(|end_time| Instant::now() >= end_time) {
+        return None;
+    }
+
+    // The player can never finish further ahead than the stable discs of the losing kind allow,
+    // the opponent's in the standard game and the player's own in anti-Othello
+    if alpha >= STABILITY_CUTOFF_ALPHA {
+        let unwanted = match board.variant {
+            constants::Variant::Standard => board.current_player.opponent(),
+            constants::Variant::Anti => board.current_player,
+        };
+        let best_possible = board.count_squares() as i32 - 2 * board.count_stable(unwanted) as i32;
+        if best_possible <= alpha {
+            return Some(best_possible);
+        }
+    }
+
+    let moves = board.get_possible_moves();
+    if moves == 0 {
+        // Neither player can move, the game is over
+        if passed {
+            return Some(final_margin(board));
+        }
+        let mut passed_board = board.clone();
+        passed_board.pass_turn();
+        return solve_node(&passed_board, -beta, -alpha, true, limit, nodes).map(|score| -score);
+    }
+
+    let mut best = -65;
+    let mut remaining = moves;
+    while remaining != 0 {
+        let current_move = remaining & remaining.wrapping_neg();
+        remaining ^= current_move;
+
+        let mut new_state = board.clone();
+        new_state.place_move(&moves, current_move);
+        let score = -solve_node(&new_state, -beta, -alpha, false, limit, nodes)?;
+        if score > best {
+            best = score;
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+    Some(best)
+}
+
+///Returns the disc margin of a finished game for the player to move, positive when the player won.
+/// Empty squares widen the margin of the winner
+pub fn final_margin(board: &board::Board) -> i32 {
+    let margin = board.disc_margin(board.current_player);
+    let empties = board.count_empty() as i32;
+    match margin {
+        m if m > 0 => m + empties,
+        m if m < 0 => m - empties,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn solves_the_four_by_four_board() {
+        // White wins 11 to 3 with perfect play, and gets the two squares left empty
+        let board: board::Board = board::Board::with_size(4).unwrap();
+        assert_eq!(solve(&board, Instant::now() + Duration::from_secs(60)), Some(-10));
+    }
+
+    // Plain negamax over every move, without the stability cutoff
+    fn full_search(board: &board::Board, passed: bool) -> i32 {
+        let moves = board.get_possible_moves();
+        if moves == 0 {
+            if passed {
+                return final_margin(board);
+            }
+            let mut passed_board = board.clone();
+            passed_board.pass_turn();
+            return -full_search(&passed_board, true);
+        }
+        let mut best = -65;
+        let mut remaining = moves;
+        while remaining != 0 {
+            let current_move = remaining & remaining.wrapping_neg();
+            remaining ^= current_move;
+            let mut new_state = board.clone();
+            new_state.place_move(&moves, current_move);
+            best = best.max(-full_search(&new_state, false));
+        }
+        best
+    }
+
+    #[test]
+    fn solves_anti_othello() {
+        let board = board::Board::with_size(4).unwrap().with_variant(constants::Variant::Anti);
+        let margin = solve(&board, Instant::now() + Duration::from_secs(60));
+        assert_eq!(margin, Some(full_search(&board, false)));
+        // A finished anti game goes to the player with fewer discs
+        let mut finished = board::Board::with_size(4).unwrap().with_variant(constants::Variant::Anti);
+        finished.black = 0x0007;
+        finished.white = 0x0f0f_0f08;
+        assert_eq!(final_margin(&finished), 10);
+        finished.pass_turn();
+        assert_eq!(final_margin(&finished), -10);
+    }
+
+    #[test]
+    fn a_node_budget_solves_or_gives_up_without_the_clock() {
+        let board: board::Board = board::Board::with_size(4).unwrap();
+        assert_eq!(solve_nodes(&board, u64::MAX), Some(-10));
+        assert_eq!(solve_nodes(&board, 10), None);
+    }
+}
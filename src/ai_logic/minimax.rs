@@ -1,217 +1,803 @@
-use std::cmp::{max, min};
-use std::fs::copy;
-use std::ptr::read_unaligned;
+use std::cmp::Reverse;
 use std::time::{Duration, Instant};
 
 //modules
 use crate::{board, constants};
+use crate::ai_logic::{endgame, mtdf, neural_network, probcut, EngineOptions, Evaluator, MoveAnalysis, SearchAlgorithm, Verdict};
+use crate::ai_logic::move_ordering::MoveOrderer;
+use crate::ai_logic::personality::{EvaluationWeights, Personality};
+use crate::ai_logic::transposition::{self, TranspositionTable};
 
 // Constants
-const POSITIONAL_WEIGHT: [i32; 64] = [
-    30, -12,  0, -1, -1,  0, -12,  30,
-    -12, -15, -3, -3, -3, -3, -15, -12,
-    0,  -3,  0, -1, -1,  0,  -3,   0,
-    -1,  -3, -1, -1, -1, -1,  -3,  -1,
-    -1,  -3, -1, -1, -1, -1,  -3,  -1,
-    0,  -3,  0, -1, -1,  0,  -3,   0,
-    -12, -15, -3, -3, -3, -3, -15, -12,
-    30, -12,  0, -1, -1,  0, -12,  30,
-];
-const CORNER_WEIGHT: i32 = 25;
-const EDGE_WEIGHT: i32 = 5;
-const CORNER_MASK: u64 = 0x8100000000000081;
-const EDGE_MASK: u64 = 0xff818181818181ff;
-
-pub fn get_minimax_move(board: board::Board, duration: u64) -> u64 {
-    let mut best_move: u64 = 0;
-    let mut last_score: i32 = std::i32::MIN;
-    //generate possible game states
+// Positional weights, corners and edges of every board size, indexed by constants::size_index
+const POSITIONAL_WEIGHTS: [[i32; 64]; 3] = [positional_weights(4), positional_weights(6), positional_weights(8)];
+const CORNER_MASKS: [u64; 3] = [ring_mask(4, true), ring_mask(6, true), ring_mask(8, true)];
+const EDGE_MASKS: [u64; 3] = [ring_mask(4, false), ring_mask(6, false), ring_mask(8, false)];
+// How many nodes are searched between checks of the clock
+const TIME_CHECK_INTERVAL: u64 = 1024;
+// Deepest iteration the search will try
+const MAX_DEPTH: i32 = 60;
+// Scores stay inside -INFINITY..INFINITY so negating them can never overflow
+pub(super) const INFINITY: i32 = 1 << 20;
+// A finished game scores WIN_SCORE plus the final disc margin, so any win beats any heuristic
+// score and bigger wins beat smaller ones
+const WIN_SCORE: i32 = 1 << 16;
+const MAX_HEURISTIC_SCORE: i32 = WIN_SCORE - 65;
+// Half width of the first aspiration window around the previous iteration's score
+const ASPIRATION_WINDOW: i32 = 40;
+// End time of searches that only stop at their depth
+const UNLIMITED_SEARCH_TIME: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+// Turns the network's estimate of the result into a score on the scale of the heuristic
+const NEURAL_SCORE_SCALE: f32 = 1000.0;
+// Entries of the table calibration searches share, they are short and run one after another
+const CALIBRATION_TABLE_SIZE: usize = 1 << 18;
 
-    //generate moves
+pub fn get_minimax_move(board: board::Board, duration: Duration, options: &EngineOptions, table: &mut TranspositionTable) -> u64 {
+    let mut search = Search::new(Instant::now() + duration, options, table);
+    let (root_moves, depth) = search.search_root(&board, false);
+    for root in &root_moves {
+        println!("Move {} scored {}", board::Board::convert_to_cords(root.chosen_move), root.score);
+    }
+    let best_move = match root_moves.first() {
+        Some(best) => {
+            print!("Playing move {} with score {} at depth {}\n\n", board::Board::convert_to_cords(best.chosen_move), best.score, depth);
+            best.chosen_move
+        }
+        None => 0,
+    };
+    search.print_stats();
+    best_move
+}
+
+///Scores every legal move of the player to move and returns them ranked from best to worst.
+/// Each move comes with the line the search expects to follow it, and a proven result
+/// when the search reaches the end of the game or the endgame solver can finish the position in time.
+///
+///  # Arguments
+///
+///  * 'board' - The position to analyze
+///  * 'duration' - The total time to spend
+///  * 'options' - The engine settings. Every move needs its own score, so the analysis always uses PVS
+///  * 'table' - The transposition table to search with
+///
+///  # Examples
+///
+/// ```
+/// let analysis = minimax::analyze_moves(board.clone(), Duration::from_secs(5), &EngineOptions::default(), &mut table);
+/// let best_move = analysis[0].chosen_move;
+/// ```
+pub fn analyze_moves(board: board::Board, duration: Duration, options: &EngineOptions, table: &mut TranspositionTable) -> Vec<MoveAnalysis> {
     let possible_moves = board.get_possible_moves();
-    //for each possible move
-    for i in 0..64 {
-        //get move bitboards
-        let move_mask: u64 = possible_moves & (1 << i);
-        if move_mask != 0 {
-            let mut copy_board: board::Board = board.clone();
-            copy_board.place_move(&possible_moves, move_mask);
-            // run minimax and pick highest score
-            let start_time = Instant::now();
-            let duration_for_move = Duration::from_secs(duration / possible_moves.count_ones() as u64);
-            let end_time = start_time + duration_for_move;
+    // Small endgames get half of the time to be solved exactly after the heuristic search
+    let solvable = board.count_empty() <= endgame::SOLVER_EMPTIES + 1;
+    let search_time = match solvable {
+        true => duration / 2,
+        false => duration,
+    };
+    let mut search = Search::new(Instant::now() + search_time, options, table);
+    let (root_moves, _) = search.search_root(&board, true);
 
-            print!("Iterating move at index: {} move {}\n", i, board::Board::convert_to_cords(move_mask));
-            let current_score = minimax(copy_board, 0, true, std::i32::MIN, std::i32::MAX, end_time);
-            print!("Move {} scored {} compared to previous best move {} with a score of {}\n\n", board::Board::convert_to_cords(move_mask), current_score, board::Board::convert_to_cords(best_move), last_score);
-            if current_score > last_score {
-                print!("new move was better, moving to best move!\n\n");
-                best_move = move_mask;
-                last_score = current_score;
+    let solver_time = (duration - search_time) / possible_moves.count_ones().max(1);
+    let mut analysis: Vec<MoveAnalysis> = Vec::new();
+    for root in root_moves {
+        let solved = match solvable {
+            true => {
+                let mut copy_board: board::Board = board.clone();
+                copy_board.place_move(&possible_moves, root.chosen_move);
+                endgame::solve(&copy_board, Instant::now() + solver_time).map(|margin| Verdict::from_margin(-margin))
             }
-        }
+            false => None,
+        };
+        let verdict = solved.or(score_verdict(root.score));
+        let mut principal_variation = vec![root.chosen_move];
+        principal_variation.extend(root.line);
+        analysis.push(MoveAnalysis { chosen_move: root.chosen_move, score: root.score, principal_variation, verdict });
+    }
+
+    analysis.sort_by_key(|entry| Reverse(entry.rank_key()));
+    analysis
+}
 
+///Runs fixed depth searches of the board at every depth from 1 to max_depth with Multi-ProbCut off.
+/// Returns the score of each depth and the best move of the deepest one.
+///
+///  # Arguments
+///
+///  * 'board' - The position to search, the player to move must have a legal move
+///  * 'max_depth' - The deepest search to run
+///
+///  # Examples
+///
+/// ```
+/// let (scores, best_move) = minimax::fixed_depth_scores(&board, 8);
+/// ```
+pub fn fixed_depth_scores(board: &board::Board, max_depth: i32) -> (Vec<i32>, u64) {
+    let options = EngineOptions { selectivity: 0, ..EngineOptions::default() };
+    let mut scores: Vec<i32> = Vec::new();
+    let mut best_move: u64 = 0;
+    let mut table = TranspositionTable::with_size(CALIBRATION_TABLE_SIZE);
+    for depth in 1..=max_depth {
+        // Every search starts from an empty table, so no score comes from a deeper search
+        let mut search = Search::unlimited(&options, &mut table);
+        let mut line: Vec<u64> = Vec::new();
+        scores.push(search.pvs(board.clone(), depth, 0, -INFINITY, INFINITY, &mut line));
+        best_move = line.first().copied().unwrap_or(0);
     }
-        print!("Playing move {} with score {}\n\n", board::Board::convert_to_cords(best_move), last_score);
-        best_move
+    (scores, best_move)
 }
 
-fn minimax(game_state: board::Board, depth: i32, maximizing_player: bool, mut alpha: i32, mut beta: i32, end_time: Instant) -> i32 {
-    if Instant::now() >= end_time || board::Board::check_game_over(&game_state) {
-        return evaluate(&game_state, maximizing_player, depth);
+///Scores every legal move of the player to move with full windows, searching no deeper than the
+/// depth limit and no more nodes than the node limit. Returns the moves of the deepest completed
+/// iteration best first.
+///
+///  # Arguments
+///
+///  * 'board' - The position to search, the player to move must have a legal move
+///  * 'depth_limit' - The deepest iteration to run
+///  * 'node_limit' - How many nodes the search may visit
+///  * 'end_time' - When the search has to stop
+///  * 'options' - The engine settings, the search always uses PVS
+///  * 'table' - The transposition table to search with
+///
+///  # Examples
+///
+/// ```
+/// let scores = minimax::limited_scores(&board, 4, 50_000, Instant::now() + Duration::from_secs(1), &options, &mut table);
+/// ```
+pub(super) fn limited_scores(board: &board::Board, depth_limit: i32, node_limit: u64, end_time: Instant, options: &EngineOptions, table: &mut TranspositionTable) -> Vec<(u64, i32)> {
+    let mut search = Search::new(end_time, options, table);
+    search.depth_limit = depth_limit.clamp(1, MAX_DEPTH);
+    search.node_limit = node_limit;
+    let (root_moves, _) = search.search_root(board, true);
+    root_moves.into_iter().map(|root| (root.chosen_move, root.score)).collect()
+}
+
+///Returns true if the score is a finished game rather than a heuristic guess
+pub fn is_win_score(score: i32) -> bool {
+    score.abs() > MAX_HEURISTIC_SCORE
+}
+
+// Scores of finished games carry the final disc margin
+fn terminal_score(margin: i32) -> i32 {
+    match margin {
+        m if m > 0 => WIN_SCORE + m,
+        m if m < 0 => -WIN_SCORE + m,
+        _ => 0,
     }
+}
 
-    let mut eval: i32 = match maximizing_player {
-        true => std::i32::MIN,
-        false => std::i32::MAX,
-    };
-    let moves = game_state.get_possible_moves();
-    // For each possible move in current state
-    for i in 0..64 {
-        let current_move = moves & (1u64 << i);
-        // If bit is a legal move
-        if current_move != 0 {
+// Turns a score back into a proven result if the search saw the end of the game
+fn score_verdict(score: i32) -> Option<Verdict> {
+    match score {
+        s if s > MAX_HEURISTIC_SCORE => Some(Verdict::Win(s - WIN_SCORE)),
+        s if s < -MAX_HEURISTIC_SCORE => Some(Verdict::Loss(-(s + WIN_SCORE))),
+        _ => None,
+    }
+}
+
+/// A root move with the score and line of the deepest completed iteration
+struct RootMove {
+    chosen_move: u64,
+    score: i32,
+    line: Vec<u64>
+}
+
+/// State shared by every node of one search. The transposition table belongs to the caller,
+/// which keeps it from one search to the next instead of allocating it every time
+pub(super) struct Search<'a> {
+    algorithm: SearchAlgorithm,
+    evaluator: Evaluator,
+    weights: EvaluationWeights,
+    end_time: Instant,
+    timed_out: bool,
+    nodes: u64,
+    // Iterative deepening stops at this depth, and the search times out after this many nodes
+    depth_limit: i32,
+    node_limit: u64,
+    table: &'a mut TranspositionTable,
+    orderer: MoveOrderer,
+    // Multi-ProbCut cut threshold, None when it is switched off
+    probcut_threshold: Option<f64>,
+    probcut_cuts: u64
+}
+
+impl<'a> Search<'a> {
+    pub(super) fn new(end_time: Instant, options: &EngineOptions, table: &'a mut TranspositionTable) -> Self {
+        table.new_search();
+        Self {
+            algorithm: options.algorithm,
+            evaluator: options.evaluator,
+            weights: options.personality.weights(),
+            end_time,
+            timed_out: false,
+            nodes: 0,
+            depth_limit: MAX_DEPTH,
+            node_limit: u64::MAX,
+            table,
+            orderer: MoveOrderer::new(),
+            probcut_threshold: probcut::threshold(options.selectivity),
+            probcut_cuts: 0,
+        }
+    }
+
+    // Iterative deepening over the root moves. Returns the moves of the deepest completed
+    // iteration best first, with that depth. Exact scores searches every root move with a full
+    // window, otherwise each iteration starts with an aspiration window around the last score
+    // and moves that can not beat the best one only get a bound.
+    fn search_root(&mut self, board: &board::Board, exact_scores: bool) -> (Vec<RootMove>, i32) {
+        let possible_moves = board.get_possible_moves();
+        let mut root_moves: Vec<RootMove> = self.orderer.order_moves(board, possible_moves, 0, 0, MAX_DEPTH)
+            .into_iter()
+            .map(|chosen_move| RootMove { chosen_move, score: -INFINITY, line: Vec::new() })
+            .collect();
+        let mut completed_depth = 0;
+
+        for depth in 1..=self.depth_limit {
+            let mut iteration = match self.algorithm {
+                SearchAlgorithm::Mtdf if !exact_scores => self.mtdf_iteration(board, &root_moves, depth),
+                _ => self.aspiration_iteration(board, &root_moves, depth, completed_depth > 0, exact_scores),
+            };
+            // An unfinished iteration is thrown away, unless nothing finished at all
+            if self.timed_out && completed_depth > 0 {
+                break;
+            }
+            // The sort is stable, so ties keep the order of the previous iteration
+            iteration.sort_by_key(|root| Reverse(root.score));
+            let searched = iteration.iter().fold(0, |moves, root| moves | root.chosen_move);
+            iteration.extend(root_moves.into_iter().filter(|root| root.chosen_move & searched == 0));
+            root_moves = iteration;
+            completed_depth = depth;
+            // Every line already reaches the end of the game
+            if self.timed_out || depth as u32 >= board.count_empty() {
+                break;
+            }
+        }
+        (root_moves, completed_depth)
+    }
+
+    // One PVS iteration. Starts with an aspiration window around the score of the previous
+    // iteration and widens it on the side the score falls out of until the score fits
+    fn aspiration_iteration(&mut self, board: &board::Board, root_moves: &[RootMove], depth: i32, has_previous: bool, exact_scores: bool) -> Vec<RootMove> {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match exact_scores || !has_previous {
+            true => (-INFINITY, INFINITY),
+            false => (root_moves[0].score - delta, root_moves[0].score + delta),
+        };
+        loop {
+            let iteration = self.search_root_moves(board, root_moves, depth, alpha, beta, exact_scores);
+            let best_score = iteration.iter().map(|root| root.score).max().unwrap_or(-INFINITY);
+            delta *= 4;
+            if self.timed_out || exact_scores {
+                return iteration;
+            } else if best_score <= alpha && alpha > -INFINITY {
+                alpha = (best_score - delta).max(-INFINITY);
+            } else if best_score >= beta && beta < INFINITY {
+                beta = (best_score + delta).min(INFINITY);
+            } else {
+                return iteration;
+            }
+        }
+    }
+
+    // One MTD(f) iteration, starting from the score of the previous one. Only the best move gets a score
+    fn mtdf_iteration(&mut self, board: &board::Board, root_moves: &[RootMove], depth: i32) -> Vec<RootMove> {
+        let first_guess = match root_moves[0].score {
+            score if score == -INFINITY => 0,
+            score => score,
+        };
+        match mtdf::mtdf(self, board, depth, first_guess) {
+            Some((score, line)) => {
+                // The root can only be cut off by the table if it was searched deeper before
+                let chosen_move = line.first().copied().unwrap_or(root_moves[0].chosen_move);
+                vec![RootMove { chosen_move, score, line: line.into_iter().skip(1).collect() }]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // One pass over the root moves in order. The first move gets the whole window and the
+    // rest a null window, with a full re-search if they turn out to be better
+    fn search_root_moves(&mut self, board: &board::Board, root_moves: &[RootMove], depth: i32, mut alpha: i32, beta: i32, exact_scores: bool) -> Vec<RootMove> {
+        let possible_moves = board.get_possible_moves();
+        let mut iteration: Vec<RootMove> = Vec::with_capacity(root_moves.len());
+        for (move_number, root) in root_moves.iter().enumerate() {
+            let mut copy_board: board::Board = board.clone();
+            copy_board.place_move(&possible_moves, root.chosen_move);
+            let mut line: Vec<u64> = Vec::new();
+            let score = if exact_scores {
+                -self.pvs(copy_board, depth - 1, 1, -INFINITY, INFINITY, &mut line)
+            } else if move_number == 0 {
+                -self.pvs(copy_board, depth - 1, 1, -beta, -alpha, &mut line)
+            } else {
+                let score = -self.pvs(copy_board.clone(), depth - 1, 1, -alpha - 1, -alpha, &mut line);
+                match score > alpha && score < beta {
+                    true => -self.pvs(copy_board, depth - 1, 1, -beta, -alpha, &mut line),
+                    false => score,
+                }
+            };
+            if self.timed_out {
+                break;
+            }
+            iteration.push(RootMove { chosen_move: root.chosen_move, score, line });
+            alpha = alpha.max(score);
+            // Failing high at the root means the aspiration window was too low
+            if alpha >= beta && !exact_scores {
+                break;
+            }
+        }
+        iteration
+    }
+
+    ///Creates a search that only stops at the depth it is given
+    pub(super) fn unlimited(options: &EngineOptions, table: &'a mut TranspositionTable) -> Self {
+        Self::new(Instant::now() + UNLIMITED_SEARCH_TIME, options, table)
+    }
+
+    ///Returns true once the search has run out of time, every score since then is unreliable
+    pub(super) fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    ///Returns how many nodes the search has visited
+    pub(super) fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    ///Negamax principal variation search to a fixed depth. Scores are from the point of view of the
+    /// player to move, and the principal variation is written to pv.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'game_state' - The position to search
+    ///  * 'depth' - The remaining depth
+    ///  * 'ply' - How far the position is from the root
+    ///  * 'alpha' - The score the player to move is already sure of
+    ///  * 'beta' - The score the opponent is already sure of
+    ///  * 'pv' - Filled with the best line found
+    pub(super) fn pvs(&mut self, game_state: board::Board, depth: i32, ply: usize, mut alpha: i32, beta: i32, pv: &mut Vec<u64>) -> i32 {
+        pv.clear();
+        self.nodes += 1;
+        if self.nodes >= self.node_limit || (self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && Instant::now() >= self.end_time) {
+            self.timed_out = true;
+        }
+        if board::Board::check_game_over(&game_state) {
+            return terminal_score(endgame::final_margin(&game_state));
+        }
+        if self.timed_out || depth <= 0 {
+            return self.evaluate(&game_state, ply);
+        }
+
+        // A null window search stops at a bound an earlier search already proved, wider windows
+        // are searched so they keep their principal variation
+        let key = transposition::hash(&game_state);
+        if beta - alpha == 1 {
+            if let Some((lower, upper)) = self.table.probe_bounds(key, depth) {
+                if lower >= beta {
+                    return lower;
+                }
+                if upper <= alpha {
+                    return upper;
+                }
+            }
+        }
+
+        let moves = game_state.get_possible_moves();
+        // No legal moves, the other player moves from the same position
+        if moves == 0 {
+            let mut passed_state = game_state.clone();
+            passed_state.pass_turn();
+            return -self.pvs(passed_state, depth, ply + 1, -beta, -alpha, pv);
+        }
+        if let Some(score) = self.probcut(&game_state, depth, ply, alpha, beta) {
+            return score;
+        }
+
+        let tt_move = self.table.probe_move(key);
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move: u64 = 0;
+        // For each possible move in current state, most promising first
+        for (move_number, current_move) in self.orderer.order_moves(&game_state, moves, tt_move, ply, depth).into_iter().enumerate() {
             //create a copy of the board and play move
             let mut new_state: board::Board = game_state.clone();
             new_state.place_move(&moves, current_move);
-            //recursively evaluate nwe game state
-            let new_eval = minimax(new_state, depth+1, !maximizing_player, alpha, beta, end_time);
-            // Check if move was better than previous best move
-            match maximizing_player {
-                true => {
-                    eval = max(new_eval, eval);
-                    alpha = max(alpha, eval);
-                    if beta <= alpha {
-                        break;
-                    }
-                }
-                false => {
-                    eval = min(new_eval, eval);
-                    beta = min(beta, eval);
+            let mut line: Vec<u64> = Vec::new();
+            // Later moves only have to prove they are no better than alpha
+            let score = if move_number == 0 {
+                -self.pvs(new_state, depth - 1, ply + 1, -beta, -alpha, &mut line)
+            } else {
+                let score = -self.pvs(new_state.clone(), depth - 1, ply + 1, -alpha - 1, -alpha, &mut line);
+                match score > alpha && score < beta {
+                    true => -self.pvs(new_state, depth - 1, ply + 1, -beta, -alpha, &mut line),
+                    false => score,
                 }
             };
+            // Keep the line of the best move so far as the principal variation
+            if score > best_score {
+                best_score = score;
+                best_move = current_move;
+                pv.clear();
+                pv.push(current_move);
+                pv.extend(line);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                self.orderer.record_cutoff(game_state.current_player, current_move, ply, depth, move_number);
+                break;
+            }
+        }
+        if !self.timed_out {
+            self.table.store(key, best_move, depth, best_score, original_alpha, beta);
+        }
+        best_score
+    }
+
+    // Score of a leaf with the evaluator the search was set up with
+    fn evaluate(&self, state: &board::Board, ply: usize) -> i32 {
+        match (self.evaluator, neural_network::network_for(state)) {
+            (Evaluator::Neural, Some(network)) => ((network.evaluate(state) * NEURAL_SCORE_SCALE) as i32).clamp(-MAX_HEURISTIC_SCORE, MAX_HEURISTIC_SCORE),
+            _ => evaluate_with(state, ply as i32, &self.weights),
+        }
+    }
+
+    // Multi-ProbCut. Shallow null window searches around the bounds the regressions predict
+    // stand in for the deep search when they show it would fail high or low with enough confidence.
+    // Only null window nodes are pruned, and searches that reach the end of the game are left
+    // alone so endgames stay exact
+    fn probcut(&mut self, state: &board::Board, depth: i32, ply: usize, alpha: i32, beta: i32) -> Option<i32> {
+        let threshold = self.probcut_threshold?;
+        if depth < probcut::MIN_DEPTH || depth as u32 >= state.count_empty() || beta - alpha > 1 {
+            return None;
         }
+        for check in probcut::table().checks(state, depth) {
+            let mut line: Vec<u64> = Vec::new();
+            let bound = check.beta_bound(beta, threshold);
+            if !is_win_score(beta) && !is_win_score(bound)
+                && self.pvs(state.clone(), check.shallow_depth, ply, bound - 1, bound, &mut line) >= bound && !self.timed_out {
+                self.probcut_cuts += 1;
+                return Some(beta);
+            }
+            let bound = check.alpha_bound(alpha, threshold);
+            if !is_win_score(alpha) && !is_win_score(bound)
+                && self.pvs(state.clone(), check.shallow_depth, ply, bound, bound + 1, &mut line) <= bound && !self.timed_out {
+                self.probcut_cuts += 1;
+                return Some(alpha);
+            }
+        }
+        None
+    }
+
+    fn print_stats(&self) {
+        println!(
+            "Searched {} nodes, {} cutoffs, first-move cutoff rate {:.1}%, {} ProbCut cuts",
+            self.nodes,
+            self.orderer.stats.cutoffs,
+            self.orderer.stats.first_move_cutoff_rate(),
+            self.probcut_cuts
+        );
     }
-    eval
 }
 
 
-fn evaluate(state: &board::Board, maximizing_player: bool, depth: i32) -> i32{
+///Returns the heuristic score of a position that is not finished, from the point of view of the player to move
+pub(super) fn evaluate(state: &board::Board, depth: i32) -> i32{
+    evaluate_with(state, depth, &Personality::Balanced.weights())
+}
+
+///Returns the heuristic score of a position that is not finished with the given weights, from the
+/// point of view of the player to move. In anti-Othello the terms that count discs and safe
+/// squares change sign, while mobility, frontier and parity are worth the same in both games
+pub(super) fn evaluate_with(state: &board::Board, depth: i32, weights: &EvaluationWeights) -> i32{
     let mut score: i32 = 0;
-    let (player, opponent, player_color, opponent_color) = match maximizing_player {
-        true => match state.current_player {
-            constants::Color::Black => (state.black, state.white, constants::Color::Black, constants::Color::White),
-            constants::Color::White => (state.white, state.black, constants::Color::White, constants::Color::Black),
-        },
-        false => match state.current_player {
-            constants::Color::Black => (state.white, state.black, constants::Color::White, constants::Color::Black),
-            constants::Color::White => (state.black, state.white, constants::Color::Black, constants::Color::White)
-        }
+    // Discs, squares and stability, which are only worth having when discs are
+    let mut material: i32 = 0;
+    let (player, opponent, player_color, opponent_color) = match state.current_player {
+        constants::Color::Black => (state.black, state.white, constants::Color::Black, constants::Color::White),
+        constants::Color::White => (state.white, state.black, constants::Color::White, constants::Color::Black),
     };
 
     // Player pieces
-    let mut piece_weight: i32 = 5;
+    let mut piece_weight: i32 = weights.discs[0];
     if depth > 10 && depth < 30{
-        piece_weight = 12;
+        piece_weight = weights.discs[1];
     }
 
     if depth > 30 {
-        piece_weight = 25;
+        piece_weight = weights.discs[2];
     }
-    let player_pieces = state.get_pieces(player_color) as i32;
-    let opponent_pieces = state.get_pieces(opponent_color) as i32;
+    let player_pieces = state.count_discs(player_color) as i32;
+    let opponent_pieces = state.count_discs(opponent_color) as i32;
     let piece_count = player_pieces - opponent_pieces;
-    score += piece_count * piece_weight;
-    // Winning position
-    if board::Board::check_game_over(&state) {
-        if player_pieces > opponent_pieces{
-            score = std::i32::MAX;
-        } else {
-            score = std::i32::MIN;
-        }
-        return score;
-    }
+    material += piece_count * piece_weight;
 
     //Mobility
-    let player_moves = count_bits(state.get_possible_moves_of_color(&player_color));
-    let opponent_moves = count_bits(state.get_possible_moves_of_color(&opponent_color));
+    let player_moves = state.count_mobility(player_color) as i32;
+    let opponent_moves = state.count_mobility(opponent_color) as i32;
     let mobility = player_moves - opponent_moves;
-    score += mobility * 10;
+    score += mobility * weights.mobility;
 
-    // Stability, Positional weight, Corners, and Edges
-    let mut stability = 0;
+    // Frontier, pieces next to empty squares hand the opponent moves
+    let frontier = state.count_frontier(player_color) as i32 - state.count_frontier(opponent_color) as i32;
+    score -= frontier * weights.frontier;
 
-    for i in 0..64{
+    // Positional weight, Corners, and Edges
+    let size = constants::size_index(state.size());
+    let mut positional: i32 = 0;
+    for (i, weight) in POSITIONAL_WEIGHTS[size].iter().enumerate() {
         let pos: u64 = 1 << i;
         if player & pos != 0 {
-            score += POSITIONAL_WEIGHT[i];
-            if is_stable(&player, &opponent, &pos){
-                stability +=1
-            }
-            if pos & CORNER_MASK != 0{
-                score += CORNER_WEIGHT;
-            } else if pos & EDGE_MASK != 0{
-                score += EDGE_WEIGHT;
+            positional += weight;
+            if pos & CORNER_MASKS[size] != 0{
+                material += weights.corner;
+            } else if pos & EDGE_MASKS[size] != 0{
+                material += weights.edge;
             }
         }else if opponent & pos != 0 {
-            score -= POSITIONAL_WEIGHT[i];
-            if is_stable(&opponent, &player, &pos) {
-                stability -= 1;
-            }
+            positional -= weight;
         }
     }
-    score += stability * 20;
-    // Return score
-    score
+    material += positional * weights.position / 100;
+
+    // Stability
+    let stability = state.count_stable(player_color) as i32 - state.count_stable(opponent_color) as i32;
+    material += stability * weights.stability;
+    score += material * state.variant.disc_sign();
+
+    // Parity, the player to move gets the last move of every region with an odd number of empty squares
+    score += weights.parity_score(state.empty_squares(), state.size());
+    // Return score, kept below the score of any finished game
+    score.clamp(-MAX_HEURISTIC_SCORE, MAX_HEURISTIC_SCORE)
+}
+
+// Corners are worth the most and the squares next to them cost, most of all the X-squares
+const fn positional_weights(size: usize) -> [i32; 64] {
+    let mut weights = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        weights[index] = match constants::edge_distances(index, size) {
+            Some((0, 0)) => 30,
+            Some((0, 1)) => -12,
+            Some((1, 1)) => -15,
+            Some((0, 2)) | Some((2, 2)) | None => 0,
+            Some((1, _)) => -3,
+            Some(_) => -1,
+        };
+        index += 1;
+    }
+    weights
 }
 
-fn count_bits(bitboard: u64) -> i32 {
-    let mut count: i32 = 0;
-    for i in 0..64 {
-        let bit = bitboard & (1 << i);
-        if bit != 0{
-            count += 1;
+// The corners of the board, or every square on its edges
+const fn ring_mask(size: usize, corners_only: bool) -> u64 {
+    let mut mask = 0;
+    let mut index = 0;
+    while index < 64 {
+        if let Some((0, far)) = constants::edge_distances(index, size) {
+            if far == 0 || !corners_only {
+                mask |= 1 << index;
+            }
         }
+        index += 1;
     }
-    count
+    mask
 }
 
-fn is_stable(color: &u64, opp_color: &u64, piece: &u64) -> bool {
-    // Check if the piece is on a stable square
-    if is_stable_square(piece) {
-        return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the lowest legal move every turn until only the given number of squares are empty
+    fn played_down_to(empty_squares: u32) -> board::Board {
+        let mut board = board::Board::new();
+        while board.count_empty() > empty_squares {
+            let moves = board.get_possible_moves();
+            match moves {
+                0 => board.pass_turn(),
+                _ => {
+                    board.place_move(&moves, moves & moves.wrapping_neg());
+                }
+            }
+        }
+        board
     }
 
-    // Check if the piece is surrounded by same-colored pieces on all sides
-    let north = (piece << 8) & opp_color;
-    let south = (piece >> 8) & opp_color;
-    let west = (piece << 1) & 0x7f7f7f7f7f7f7f7f & opp_color;
-    let east = (piece >> 1) & 0xfefefefefefefefe & opp_color;
-    let nw = (piece << 9) & 0xfefefefefefefefe & opp_color;
-    let ne = (piece << 7) & 0x7f7f7f7f7f7f7f00 & opp_color;
-    let sw = (piece >> 7) & 0x00fefefefefefefe & opp_color;
-    let se = (piece >> 9) & 0x007f7f7f7f7f7f7f & opp_color;
+    // Plain fail-hard alpha-beta without a table, move ordering or pruning, the reference the
+    // search is checked against
+    fn alpha_beta(board: &board::Board, depth: i32, ply: i32, mut alpha: i32, beta: i32, weights: &EvaluationWeights) -> i32 {
+        if board::Board::check_game_over(board) {
+            return terminal_score(endgame::final_margin(board));
+        }
+        if depth <= 0 {
+            return evaluate_with(board, ply, weights);
+        }
+        let moves = board.get_possible_moves();
+        if moves == 0 {
+            let mut passed = board.clone();
+            passed.pass_turn();
+            return -alpha_beta(&passed, depth, ply + 1, -beta, -alpha, weights);
+        }
+        let mut remaining = moves;
+        while remaining != 0 {
+            let current_move = remaining & remaining.wrapping_neg();
+            remaining ^= current_move;
+            let mut new_state = board.clone();
+            new_state.place_move(&moves, current_move);
+            alpha = alpha.max(-alpha_beta(&new_state, depth - 1, ply + 1, -beta, -alpha, weights));
+            if alpha >= beta {
+                break;
+            }
+        }
+        alpha
+    }
 
-    if north == 0 && south == 0 && west == 0 && east == 0 && nw == 0 && ne == 0 && sw == 0 && se == 0 {
-        return true;
+    // Positions from the opening to the endgame, the last ones with passes close by
+    fn test_positions() -> Vec<board::Board> {
+        vec![board::Board::new(), played_down_to(50), played_down_to(36), played_down_to(14)]
     }
 
-    // If neither condition is met, the piece is unstable
-    false
-}
+    // Multi-ProbCut off, so the search is exact
+    fn exact_options() -> EngineOptions {
+        EngineOptions { selectivity: 0, ..EngineOptions::default() }
+    }
+
+    #[test]
+    fn pvs_and_aspiration_windows_match_alpha_beta() {
+        let options = exact_options();
+        let weights = options.personality.weights();
+        let mut table = TranspositionTable::with_size(1 << 16);
+        for board in test_positions() {
+            for depth in 1..=5 {
+                let expected = alpha_beta(&board, depth, 0, -INFINITY, INFINITY, &weights);
+                let mut search = Search::unlimited(&options, &mut table);
+                assert_eq!(search.pvs(board.clone(), depth, 0, -INFINITY, INFINITY, &mut Vec::new()), expected);
 
-fn is_stable_square(piece: &u64) -> bool {
-    let corners = 0x8100000000000081;
-    let edges = 0x7e0000000000007e;
-    let x_squares = 0x42c300000000c342;
-    let c_squares = 0x3c42424242423c00;
+                // Iterative deepening with aspiration windows and null window searches of the later moves
+                let mut search = Search::unlimited(&options, &mut table);
+                search.depth_limit = depth;
+                let (root_moves, completed_depth) = search.search_root(&board, false);
+                assert_eq!((root_moves[0].score, completed_depth), (expected, depth));
 
-    // Check if the piece is on a stable square
-    if (corners & piece) != 0 || (edges & piece) != 0 || (x_squares & piece) != 0 || (c_squares & piece) != 0 {
-        return true;
+                // Exact scores give every root move its own alpha-beta value
+                let mut search = Search::unlimited(&options, &mut table);
+                search.depth_limit = depth;
+                let possible_moves = board.get_possible_moves();
+                for root in search.search_root(&board, true).0 {
+                    let mut child = board.clone();
+                    child.place_move(&possible_moves, root.chosen_move);
+                    assert_eq!(root.score, -alpha_beta(&child, depth - 1, 1, -INFINITY, INFINITY, &weights));
+                }
+            }
+        }
     }
 
-    // If not, the piece is not stable
-    false
-}
+    #[test]
+    fn mtdf_matches_alpha_beta() {
+        let options = EngineOptions { algorithm: SearchAlgorithm::Mtdf, ..exact_options() };
+        let weights = options.personality.weights();
+        let mut table = TranspositionTable::with_size(1 << 16);
+        for board in test_positions() {
+            for depth in 1..=5 {
+                let expected = alpha_beta(&board, depth, 0, -INFINITY, INFINITY, &weights);
+                // The first guess only changes how many passes it takes
+                for first_guess in [0, expected, expected - 150, expected + 37] {
+                    let mut search = Search::unlimited(&options, &mut table);
+                    assert_eq!(mtdf::mtdf(&mut search, &board, depth, first_guess).map(|(score, _)| score), Some(expected));
+                }
+                let mut search = Search::unlimited(&options, &mut table);
+                search.depth_limit = depth;
+                assert_eq!(search.search_root(&board, false).0[0].score, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_probcut_is_exact_once_the_search_sees_the_end() {
+        let options = EngineOptions { selectivity: probcut::max_selectivity(), ..EngineOptions::default() };
+        let weights = options.personality.weights();
+        let mut table = TranspositionTable::with_size(1 << 16);
+        let board = played_down_to(9);
+        let expected = alpha_beta(&board, 9, 0, -INFINITY, INFINITY, &weights);
+        let mut search = Search::unlimited(&options, &mut table);
+        assert_eq!(search.pvs(board, 9, 0, -INFINITY, INFINITY, &mut Vec::new()), expected);
+        assert_eq!(search.probcut_cuts, 0);
+
+        // In the midgame it cuts, and the cuts are guesses that keep the score close to the exact one
+        let board = played_down_to(40);
+        let expected = alpha_beta(&board, 6, 0, -INFINITY, INFINITY, &weights);
+        let mut search = Search::unlimited(&options, &mut table);
+        let score = search.pvs(board, 6, 0, -INFINITY, INFINITY, &mut Vec::new());
+        assert!(search.probcut_cuts > 0);
+        assert!((score - expected).abs() <= 50, "{} against {}", score, expected);
+    }
+
+    #[test]
+    fn square_tables_follow_the_board_size() {
+        #[rustfmt::skip]
+        let classic: [i32; 64] = [
+            30, -12,  0, -1, -1,  0, -12,  30,
+            -12, -15, -3, -3, -3, -3, -15, -12,
+            0,  -3,  0, -1, -1,  0,  -3,   0,
+            -1,  -3, -1, -1, -1, -1,  -3,  -1,
+            -1,  -3, -1, -1, -1, -1,  -3,  -1,
+            0,  -3,  0, -1, -1,  0,  -3,   0,
+            -12, -15, -3, -3, -3, -3, -15, -12,
+            30, -12,  0, -1, -1,  0, -12,  30,
+        ];
+        assert_eq!(POSITIONAL_WEIGHTS[constants::size_index(8)], classic);
+        assert_eq!(CORNER_MASKS[constants::size_index(8)], 0x8100_0000_0000_0081);
+        assert_eq!(EDGE_MASKS[constants::size_index(8)], 0xff81_8181_8181_81ff);
+        assert_eq!(CORNER_MASKS[constants::size_index(6)], 1 | 1 << 5 | 1 << 40 | 1 << 45);
+        assert_eq!(EDGE_MASKS[constants::size_index(4)].count_ones(), 12);
+    }
 
+    #[test]
+    fn a_six_by_six_corner_ranks_above_its_x_square() {
+        let start: board::Board = board::Board::with_size(6).unwrap();
+        let with_disc_on = |square: u32| board::Board { black: start.black | 1 << square, ..start.clone() };
+        let balanced = Personality::Balanced.weights();
+        // Only the square terms, which have to treat the four corners alike
+        let squares_only = EvaluationWeights { discs: [0; 3], mobility: 0, frontier: 0, stability: 0, parity: 0, ..balanced };
+        let mut square_scores = Vec::new();
+        // Every corner of the 6x6 board with the square diagonally next to it
+        for (corner, x_square) in [(0, 9), (5, 12), (40, 33), (45, 36)] {
+            for weights in [&balanced, &squares_only] {
+                let corner_score = evaluate_with(&with_disc_on(corner), 4, weights);
+                let x_square_score = evaluate_with(&with_disc_on(x_square), 4, weights);
+                assert!(corner_score > x_square_score, "{} {} {}", corner, corner_score, x_square_score);
+            }
+            square_scores.push((evaluate_with(&with_disc_on(corner), 4, &squares_only), evaluate_with(&with_disc_on(x_square), 4, &squares_only)));
+        }
+        assert!(square_scores.windows(2).all(|pair| pair[0] == pair[1]), "{:?}", square_scores);
+    }
+
+    #[test]
+    fn anti_othello_turns_the_disc_terms_around() {
+        let weights = Personality::Balanced.weights();
+        for variant in [constants::Variant::Standard, constants::Variant::Anti] {
+            let start = board::Board::new().with_variant(variant);
+            let with_disc_on = |square: u32| board::Board { black: start.black | 1 << square, ..start.clone() };
+            // A disc on the a1 corner against one on the b2 X-square
+            let corner_ahead = evaluate_with(&with_disc_on(0), 4, &weights) > evaluate_with(&with_disc_on(9), 4, &weights);
+            assert_eq!(corner_ahead, variant == constants::Variant::Standard);
+        }
+    }
+
+    #[test]
+    fn a_reused_table_searches_like_a_new_one() {
+        let options = EngineOptions::default();
+        let end_time = Instant::now() + UNLIMITED_SEARCH_TIME;
+        let board = played_down_to(44);
+        let fresh = limited_scores(&board, 5, u64::MAX, end_time, &options, &mut TranspositionTable::with_size(1 << 16));
+        // Fill the table with another position, and with this one to a greater depth
+        let mut table = TranspositionTable::with_size(1 << 16);
+        limited_scores(&played_down_to(50), 5, u64::MAX, end_time, &options, &mut table);
+        limited_scores(&board, 7, u64::MAX, end_time, &options, &mut table);
+        assert_eq!(limited_scores(&board, 5, u64::MAX, end_time, &options, &mut table), fresh);
+    }
+
+    #[test]
+    fn hints_are_legal_and_ranked_best_first() {
+        for board in [board::Board::new(), played_down_to(40)] {
+            let analysis = analyze_moves(board.clone(), Duration::from_millis(200), &EngineOptions::default(), &mut TranspositionTable::with_size(1 << 16));
+            let hinted = analysis.iter().fold(0, |moves, entry| moves | entry.chosen_move);
+            assert_eq!(analysis.len() as u32, board.get_possible_moves().count_ones());
+            assert_eq!(hinted, board.get_possible_moves());
+            assert!(analysis.windows(2).all(|pair| pair[0].rank_key() >= pair[1].rank_key()));
+            assert!(analysis.iter().all(|entry| entry.principal_variation[0] == entry.chosen_move));
+        }
+    }
+
+    #[test]
+    fn endgame_hints_rank_the_solved_best_move_first() {
+        let board = played_down_to(10);
+        let possible_moves = board.get_possible_moves();
+        assert_ne!(possible_moves, 0);
+        let analysis = analyze_moves(board.clone(), Duration::from_secs(10), &EngineOptions::default(), &mut TranspositionTable::with_size(1 << 16));
+        let best_margin = analysis.iter().map(|entry| {
+            let mut copy_board = board.clone();
+            copy_board.place_move(&possible_moves, entry.chosen_move);
+            -endgame::solve(&copy_board, Instant::now() + Duration::from_secs(10)).unwrap()
+        }).max().unwrap();
+        assert_eq!(analysis[0].verdict, Some(Verdict::from_margin(best_margin)));
+    }
+}
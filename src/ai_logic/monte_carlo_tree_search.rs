@@ -0,0 +1,858 @@
+//Imports
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+//Modules
+use crate::{board, constants};
+use crate::seed::{self, GameRng};
+use crate::ai_logic::{endgame, minimax, EngineOptions, Evaluator};
+use crate::ai_logic::move_ordering::MoveOrderer;
+use crate::ai_logic::neural_network::{self, Network};
+
+// Exploration constant of the PUCT formula, higher values spread the playouts over more moves
+pub const DEFAULT_EXPLORATION: f32 = 2.0;
+// Random moves a rollout plays before the position is scored with evaluate
+pub const DEFAULT_ROLLOUT_CUTOFF: usize = 20;
+// Heuristic scores this far apart are roughly one unit apart in value
+const HEURISTIC_VALUE_SCALE: f32 = 300.0;
+// Each move down the move ordering gets this share of the prior of the move before it
+const ORDERING_PRIOR_DECAY: f32 = 0.85;
+// How many playouts run between checks of the clock, until the endgame solver starts working on
+// leaves. After that every playout checks it, as a single leaf solve can take LEAF_SOLVE_TIME
+const TIME_CHECK_INTERVAL: usize = 64;
+// New leaves with this many empty squares or fewer are handed to the endgame solver
+pub const DEFAULT_SOLVER_EMPTIES: u32 = 10;
+// How long the endgame solver may spend on one leaf before the leaf is scored normally
+const LEAF_SOLVE_TIME: Duration = Duration::from_millis(20);
+// The same limit for searches that run a fixed number of playouts, in nodes so that seeded runs
+// solve the same leaves on every machine
+const LEAF_SOLVE_NODES: u64 = 50_000;
+// Most nodes the tree holds before old subtrees are recycled, about 80 MB
+pub const DEFAULT_MAX_NODES: usize = 1 << 20;
+// The root always needs room for all of its children
+const MINIMUM_NODES: usize = 65;
+// When the tree is full, subtrees are recycled until this share of the budget is free
+const PRUNE_DIVISOR: usize = 4;
+
+/// Where the tree search gets the prior of every move from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PriorSource {
+    // Every move is equally likely
+    Uniform,
+    // Moves the minimax move ordering tries first get more of the prior
+    MoveOrdering,
+    // The policy head of the network, or the move ordering without one
+    Network
+}
+
+/// How the tree search scores a new leaf
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LeafEvaluation {
+    // Random moves up to the rollout cutoff, then evaluate
+    Rollout,
+    // minimax::evaluate on the leaf itself
+    Evaluate,
+    // The value output of the network, or evaluate without one
+    Network
+}
+
+/// How much of the selection value comes from the all-moves-as-first statistics (RAVE) of a move,
+/// as a function of its visits. The share starts at 1 and falls as real visits come in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RaveSchedule {
+    // sqrt(k / (3n + k)): the AMAF and real values weigh the same at k visits
+    Equivalence(f32),
+    // Silver's minimum squared error schedule for the given bias of the AMAF values
+    MinimumError(f32)
+}
+
+impl RaveSchedule {
+    // The share of the AMAF value for a move with the given real and AMAF visits
+    fn beta(self, visits: u32, amaf_visits: u32) -> f32 {
+        let (visits, amaf_visits) = (visits as f32, amaf_visits as f32);
+        match self {
+            RaveSchedule::Equivalence(k) => (k / (3.0 * visits + k)).sqrt(),
+            RaveSchedule::MinimumError(bias) => amaf_visits / (visits + amaf_visits + 4.0 * bias * bias * visits * amaf_visits),
+        }
+    }
+}
+
+/// Settings of the tree search, chosen on the command line
+#[derive(Clone, Copy, Debug)]
+pub struct MctsSettings {
+    pub exploration: f32,
+    pub priors: PriorSource,
+    pub leaf: LeafEvaluation,
+    pub rollout_cutoff: usize,
+    // Leaves with this many empty squares or fewer are solved exactly, 0 switches the solver off
+    pub solver_empties: u32,
+    // All-moves-as-first statistics are kept and mixed into selection when set
+    pub rave: Option<RaveSchedule>,
+    // Node budget of the tree, the arena is allocated up front with room for this many
+    pub max_nodes: usize
+}
+
+impl Default for MctsSettings {
+    fn default() -> Self {
+        Self { exploration: DEFAULT_EXPLORATION, priors: PriorSource::MoveOrdering, leaf: LeafEvaluation::Evaluate, rollout_cutoff: DEFAULT_ROLLOUT_CUTOFF, solver_empties: DEFAULT_SOLVER_EMPTIES, rave: None, max_nodes: DEFAULT_MAX_NODES }
+    }
+}
+
+impl MctsSettings {
+    ///Returns settings that take priors and leaf values from a network, as self-play does
+    pub fn network() -> Self {
+        Self { priors: PriorSource::Network, leaf: LeafEvaluation::Network, ..Self::default() }
+    }
+}
+
+///Runs the tree search on the board until the time is up or the root is proven, and returns the
+/// best move. The network is used when the engine options select the neural evaluator.
+///
+///  # Arguments
+///
+///  * 'board' - The current game-state
+///  * 'duration' - How long to search
+///  * 'options' - The engine settings
+///  * 'rng' - The random number generator the tree's seed is drawn from
+///
+///  # Examples
+///
+/// ```
+/// let selected_move = monte_carlo_tree_search::get_mcts_move(board, Duration::from_secs(5), &options, &mut rng);
+/// ```
+pub fn get_mcts_move(board: board::Board, duration: Duration, options: &EngineOptions, rng: &mut GameRng) -> u64 {
+    let end_time = Instant::now() + duration;
+    let network = match options.evaluator {
+        Evaluator::Neural => neural_network::network_for(&board),
+        Evaluator::Heuristic => None,
+    };
+    let mut tree = Tree::new(board.clone(), options.mcts, network, rng.gen());
+    let playouts = tree.run_until(end_time);
+    let root_player = board.current_player;
+    let mut children: Vec<&Node> = tree.nodes[0].children().map(|child| &tree.nodes[child]).collect();
+    children.sort_by_key(|child| std::cmp::Reverse(child.visits));
+    for child in children {
+        let proof = match child.proof_for(root_player) {
+            Some(Proof::Win) => " (proven win)",
+            Some(Proof::Draw) => " (proven draw)",
+            Some(Proof::Loss) => " (proven loss)",
+            None => "",
+        };
+        println!("Move {} visited {} times, value {:.3}{}", board::Board::convert_to_cords(child.last_move), child.visits, child.value_sum / child.visits.max(1) as f32, proof);
+    }
+    let best_move = tree.best_move();
+    print!("Playing move {} after {} playouts, {} nodes in the tree ({:.1} MB, {} recycled)\n\n", board::Board::convert_to_cords(best_move), playouts,
+        tree.size(), tree.memory_use() as f64 / (1024.0 * 1024.0), tree.recycled());
+    best_move
+}
+
+/// A result proven for the player to move in a node
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Proof {
+    Loss,
+    Draw,
+    Win
+}
+
+impl Proof {
+    fn from_margin(margin: i32) -> Self {
+        match margin {
+            m if m > 0 => Proof::Win,
+            m if m < 0 => Proof::Loss,
+            _ => Proof::Draw,
+        }
+    }
+
+    fn value(self) -> f32 {
+        match self {
+            Proof::Win => 1.0,
+            Proof::Draw => 0.0,
+            Proof::Loss => -1.0,
+        }
+    }
+
+    fn reversed(self) -> Self {
+        match self {
+            Proof::Win => Proof::Loss,
+            Proof::Draw => Proof::Draw,
+            Proof::Loss => Proof::Win,
+        }
+    }
+}
+
+/// A position in the tree
+struct Node {
+    board: board::Board,
+    // The move that led here, 0 for the root and for passes
+    last_move: u64,
+    prior: f32,
+    visits: u32,
+    // Sum of the playout values for the player who moved into this node
+    value_sum: f32,
+    // Playouts from the parent in which the player to move there played this move at any later point
+    amaf_visits: u32,
+    amaf_value_sum: f32,
+    // The last playout that passed through this node, old subtrees are recycled first
+    last_visit: u32,
+    // Children are stored next to each other
+    first_child: usize,
+    child_count: usize,
+    expanded: bool,
+    // The result for the player to move here once the game is over or the subtree is solved
+    proof: Option<Proof>
+}
+
+impl Node {
+    fn new(board: board::Board, last_move: u64, prior: f32) -> Self {
+        Self { board, last_move, prior, visits: 0, value_sum: 0.0, amaf_visits: 0, amaf_value_sum: 0.0, last_visit: 0, first_child: 0, child_count: 0, expanded: false, proof: None }
+    }
+
+    // The proven result for the given player, passes can leave either player to move
+    fn proof_for(&self, player: constants::Color) -> Option<Proof> {
+        match self.board.current_player == player {
+            true => self.proof,
+            false => self.proof.map(Proof::reversed),
+        }
+    }
+
+    fn children(&self) -> std::ops::Range<usize> {
+        self.first_child..self.first_child + self.child_count
+    }
+}
+
+/// A PUCT search tree. Every playout walks down to a leaf picking the child with the best
+/// average value plus an exploration bonus that grows with its prior and shrinks with its visits,
+/// scores the leaf and adds it to the tree.
+/// Finished games and leaves the endgame solver can finish carry a proven result, which is passed
+/// up the tree: a node is a proven win when one move wins, and a proven loss or draw once every move
+/// is proven. Selection and the final move choice trust proofs over the playout averages.
+/// The nodes live in an arena sized by the node budget. Once it is full, the subtrees the playouts
+/// left longest ago are cut back to single leaves that keep their statistics, and their nodes are
+/// recycled for new children.
+pub struct Tree<'a> {
+    nodes: Vec<Node>,
+    // Freed blocks of children by their length
+    free_blocks: Vec<Vec<usize>>,
+    free_nodes: usize,
+    recycled: usize,
+    // Number of the current playout
+    stamp: u32,
+    settings: MctsSettings,
+    network: Option<&'a Network>,
+    orderer: MoveOrderer,
+    // Squares each color played in the last rollout, for the AMAF statistics
+    rollout_moves: [u64; 2],
+    // Draws the rollout moves
+    rng: GameRng,
+    // When a timed search has to stop, leaf solves never run past it
+    end_time: Option<Instant>,
+    // Set once a leaf was handed to the endgame solver
+    solving: bool
+}
+
+impl<'a> Tree<'a> {
+    ///Creates a tree with only the root position
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The root position, the player to move must have a legal move
+    ///  * 'settings' - The exploration constant and where priors and leaf values come from
+    ///  * 'network' - The network for network priors and leaf values, if there is one
+    ///  * 'seed' - The seed of the rollouts
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let mut tree = Tree::new(board.clone(), MctsSettings::network(), Some(&network), rng.gen());
+    /// ```
+    pub fn new(board: board::Board, settings: MctsSettings, network: Option<&'a Network>, seed: u64) -> Self {
+        let settings = MctsSettings { max_nodes: settings.max_nodes.max(MINIMUM_NODES), ..settings };
+        let mut nodes = Vec::with_capacity(settings.max_nodes);
+        nodes.push(Node::new(board, 0, 1.0));
+        Self { nodes, free_blocks: vec![Vec::new(); MINIMUM_NODES], free_nodes: 0, recycled: 0, stamp: 0, settings, network, orderer: MoveOrderer::new(), rollout_moves: [0; 2], rng: seed::rng(seed), end_time: None, solving: false }
+    }
+
+    ///Returns the number of nodes in the tree
+    pub fn size(&self) -> usize {
+        self.nodes.len() - self.free_nodes
+    }
+
+    ///Returns the bytes held by the node arena
+    pub fn memory_use(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+    }
+
+    ///Returns how many nodes have been freed for reuse so far
+    pub fn recycled(&self) -> usize {
+        self.recycled
+    }
+
+    ///Runs the given number of playouts from the root
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'playouts' - How many playouts to run
+    pub fn run(&mut self, playouts: usize) {
+        for _ in 0..playouts {
+            self.playout();
+        }
+    }
+
+    ///Runs playouts until the end time or until the root is proven, and at least one.
+    /// Returns how many playouts ran
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'end_time' - When the search has to stop
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let playouts = tree.run_until(Instant::now() + Duration::from_secs(5));
+    /// ```
+    pub fn run_until(&mut self, end_time: Instant) -> usize {
+        self.end_time = Some(end_time);
+        let mut playouts: usize = 0;
+        while self.nodes[0].proof.is_none() {
+            let check_clock = playouts > 0 && (self.solving || playouts.is_multiple_of(TIME_CHECK_INTERVAL));
+            if check_clock && Instant::now() >= end_time {
+                break;
+            }
+            self.playout();
+            playouts += 1;
+        }
+        self.end_time = None;
+        playouts
+    }
+
+    ///Mixes Dirichlet noise into the priors of the root's children so self-play keeps trying moves
+    /// the guide does not like yet. Expands the root first if needed.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'alpha' - The concentration of the Dirichlet distribution, smaller values put the noise on fewer moves
+    ///  * 'fraction' - How much of each prior is replaced by noise
+    ///  * 'rng' - The random number generator to draw the noise from
+    pub fn add_root_noise<R: Rng>(&mut self, alpha: f32, fraction: f32, rng: &mut R) {
+        if !self.nodes[0].expanded {
+            self.expand(0);
+        }
+        let children = self.nodes[0].children();
+        let noise: Vec<f32> = children.clone().map(|_| sample_gamma(alpha, rng)).collect();
+        let total: f32 = noise.iter().sum::<f32>().max(f32::MIN_POSITIVE);
+        for (child, noise) in children.zip(noise) {
+            let prior = self.nodes[child].prior;
+            self.nodes[child].prior = (1.0 - fraction) * prior + fraction * noise / total;
+        }
+    }
+
+    ///Returns the share of the root's visits each square got
+    pub fn visit_distribution(&self) -> [f32; 64] {
+        let mut distribution = [0.0; 64];
+        let total: u32 = self.nodes[0].children().map(|child| self.nodes[child].visits).sum();
+        for child in self.nodes[0].children().map(|child| &self.nodes[child]) {
+            if child.last_move != 0 {
+                distribution[child.last_move.trailing_zeros() as usize] = child.visits as f32 / total.max(1) as f32;
+            }
+        }
+        distribution
+    }
+
+    // The root move with the most visits, or 0 if the root has no children yet
+    fn most_visited_move(&self) -> u64 {
+        self.nodes[0].children()
+            .max_by_key(|&child| self.nodes[child].visits)
+            .map_or(0, |child| self.nodes[child].last_move)
+    }
+
+    ///Returns a proven winning root move if there is one, otherwise the most visited unproven move,
+    /// or a proven draw when the unproven moves look worse than it. Returns 0 if the root has no children yet
+    pub fn best_move(&self) -> u64 {
+        let player = self.nodes[0].board.current_player;
+        let most_visited = |proof: Option<Proof>| self.nodes[0].children()
+            .filter(|&child| self.nodes[child].proof_for(player) == proof)
+            .max_by_key(|&child| self.nodes[child].visits);
+        let chosen = match (most_visited(Some(Proof::Win)), most_visited(None), most_visited(Some(Proof::Draw))) {
+            (Some(win), _, _) => Some(win),
+            (None, Some(unproven), Some(draw)) => match self.nodes[unproven].value_sum >= 0.0 {
+                true => Some(unproven),
+                false => Some(draw),
+            },
+            (None, unproven, draw) => unproven.or(draw),
+        };
+        chosen.map_or_else(|| self.most_visited_move(), |child| self.nodes[child].last_move)
+    }
+
+    ///Returns a root move drawn with probability proportional to its visits
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'rng' - The random number generator to draw with
+    pub fn sample_move<R: Rng>(&self, rng: &mut R) -> u64 {
+        let total: u32 = self.nodes[0].children().map(|child| self.nodes[child].visits).sum();
+        if total == 0 {
+            return self.most_visited_move();
+        }
+        let mut target = rng.gen_range(0..total);
+        for child in self.nodes[0].children() {
+            if target < self.nodes[child].visits {
+                return self.nodes[child].last_move;
+            }
+            target -= self.nodes[child].visits;
+        }
+        self.most_visited_move()
+    }
+
+    // Walks down to a leaf or a proven node, scores it and adds the result to every node on the way
+    fn playout(&mut self) {
+        self.stamp += 1;
+        self.nodes[0].last_visit = self.stamp;
+        let mut path = vec![0];
+        let mut node = 0;
+        while self.nodes[node].expanded && self.nodes[node].proof.is_none() {
+            node = self.select_child(node);
+            self.nodes[node].last_visit = self.stamp;
+            path.push(node);
+        }
+        // Value for the player to move at the leaf
+        let value = match self.nodes[node].proof {
+            Some(proof) => proof.value(),
+            None => {
+                let value = self.expand(node);
+                match self.nodes[node].proof {
+                    Some(proof) => proof.value(),
+                    None => value,
+                }
+            }
+        };
+        if self.nodes[node].proof.is_some() {
+            self.prove_path(&path);
+        }
+
+        let leaf_player = self.nodes[node].board.current_player;
+        self.nodes[0].visits += 1;
+        for pair in path.windows(2) {
+            let mover = self.nodes[pair[0]].board.current_player;
+            let child = &mut self.nodes[pair[1]];
+            child.visits += 1;
+            child.value_sum += match mover == leaf_player {
+                true => value,
+                false => -value,
+            };
+        }
+        if self.settings.rave.is_some() {
+            self.update_amaf(&path, value, leaf_player);
+        }
+    }
+
+    // Credits every move played later in the playout by the same player to the sibling that plays
+    // it right away. Walks up from the leaf so each node sees the moves played below it
+    fn update_amaf(&mut self, path: &[usize], value: f32, leaf_player: constants::Color) {
+        let mut played = std::mem::take(&mut self.rollout_moves);
+        for pair in path.windows(2).rev() {
+            let mover = self.nodes[pair[0]].board.current_player;
+            played[color_index(mover)] |= self.nodes[pair[1]].last_move;
+            let value = match mover == leaf_player {
+                true => value,
+                false => -value,
+            };
+            for child in self.nodes[pair[0]].children() {
+                let child = &mut self.nodes[child];
+                if child.last_move & played[color_index(mover)] != 0 {
+                    child.amaf_visits += 1;
+                    child.amaf_value_sum += value;
+                }
+            }
+        }
+    }
+
+    // Passes proofs up the path from its end for as long as they settle the parent
+    fn prove_path(&mut self, path: &[usize]) {
+        for &node in path.iter().rev().skip(1) {
+            let player = self.nodes[node].board.current_player;
+            let proofs: Vec<Option<Proof>> = self.nodes[node].children().map(|child| self.nodes[child].proof_for(player)).collect();
+            let proof = match proofs.contains(&Some(Proof::Win)) {
+                true => Some(Proof::Win),
+                false => proofs.iter().copied().collect::<Option<Vec<Proof>>>().and_then(|proofs| proofs.into_iter().max()),
+            };
+            match proof {
+                Some(proof) => self.nodes[node].proof = Some(proof),
+                None => break,
+            }
+        }
+    }
+
+    // The child with the highest average value plus exploration bonus. Unvisited children count as
+    // even, proven children count as their result and proven losses are never picked
+    fn select_child(&self, node: usize) -> usize {
+        let parent = &self.nodes[node];
+        let player = parent.board.current_player;
+        let exploration = self.settings.exploration * (parent.visits.max(1) as f32).sqrt();
+        let score = |child: &Node| {
+            let average = match (child.proof_for(player), child.visits) {
+                (Some(Proof::Loss), _) => return f32::NEG_INFINITY,
+                (Some(Proof::Win), _) => return f32::INFINITY,
+                (Some(Proof::Draw), _) => 0.0,
+                (None, 0) => 0.0,
+                (None, visits) => child.value_sum / visits as f32,
+            };
+            let average = match (self.settings.rave, child.amaf_visits, child.proof) {
+                (Some(schedule), amaf_visits, None) if amaf_visits > 0 => {
+                    let beta = schedule.beta(child.visits, amaf_visits);
+                    (1.0 - beta) * average + beta * child.amaf_value_sum / amaf_visits as f32
+                }
+                _ => average,
+            };
+            average + exploration * child.prior / (1 + child.visits) as f32
+        };
+        parent.children()
+            .max_by(|&first, &second| score(&self.nodes[first]).total_cmp(&score(&self.nodes[second])))
+            .unwrap_or(node)
+    }
+
+    // Adds the children of a leaf with their priors and returns the value of the leaf. Finished
+    // games and leaves the endgame solver can finish are proven instead, the root is always expanded
+    fn expand(&mut self, node: usize) -> f32 {
+        let board = self.nodes[node].board.clone();
+        if board::Board::check_game_over(&board) {
+            self.nodes[node].proof = Some(Proof::from_margin(endgame::final_margin(&board)));
+            return 0.0;
+        }
+        if node != 0 && board.count_empty() <= self.settings.solver_empties {
+            self.solving = true;
+            let margin = match self.end_time {
+                Some(end_time) => endgame::solve(&board, end_time.min(Instant::now() + LEAF_SOLVE_TIME)),
+                None => endgame::solve_nodes(&board, LEAF_SOLVE_NODES),
+            };
+            if let Some(margin) = margin {
+                self.nodes[node].proof = Some(Proof::from_margin(margin));
+                return 0.0;
+            }
+        }
+        let moves = board.get_possible_moves();
+        let (network_value, logits) = match (self.network, self.settings.priors, self.settings.leaf) {
+            (Some(network), PriorSource::Network, _) | (Some(network), _, LeafEvaluation::Network) => {
+                let (value, logits) = network.predict(&board);
+                (Some(value), logits)
+            }
+            _ => (None, None),
+        };
+        let value = match (self.settings.leaf, network_value) {
+            (LeafEvaluation::Network, Some(value)) => value,
+            (LeafEvaluation::Rollout, _) => self.rollout(&board),
+            _ => heuristic_value(&board),
+        };
+
+        let mut children = Vec::new();
+        if moves == 0 {
+            // The only way on is to pass
+            let mut passed = board.clone();
+            passed.pass_turn();
+            children.push(Node::new(passed, 0, 1.0));
+        } else {
+            let squares = self.prior_order(&board, moves);
+            let priors = match (self.settings.priors, logits) {
+                (PriorSource::Network, Some(logits)) => neural_network::softmax(&squares.iter().map(|square| logits[square.trailing_zeros() as usize]).collect::<Vec<f32>>()),
+                (PriorSource::Uniform, _) => vec![1.0 / squares.len() as f32; squares.len()],
+                _ => {
+                    let weights: Vec<f32> = (0..squares.len()).map(|rank| ORDERING_PRIOR_DECAY.powi(rank as i32)).collect();
+                    let total: f32 = weights.iter().sum();
+                    weights.iter().map(|weight| weight / total).collect()
+                }
+            };
+            for (square, prior) in squares.into_iter().zip(priors) {
+                let mut child = board.clone();
+                child.place_move(&moves, square);
+                children.push(Node::new(child, square, prior));
+            }
+        }
+        // Without room the node stays a leaf and is tried again on its next visit
+        let child_count = children.len();
+        let Some(first_child) = self.allocate(child_count) else {
+            return value;
+        };
+        for (index, child) in (first_child..).zip(children) {
+            match index == self.nodes.len() {
+                true => self.nodes.push(child),
+                false => self.nodes[index] = child,
+            }
+        }
+        let parent = &mut self.nodes[node];
+        parent.first_child = first_child;
+        parent.child_count = child_count;
+        parent.expanded = true;
+        value
+    }
+
+    // The start of a free block of the given length, recycling old subtrees when the budget is used up
+    fn allocate(&mut self, length: usize) -> Option<usize> {
+        if let Some(start) = self.take_free_block(length) {
+            return Some(start);
+        }
+        if self.nodes.len() + length > self.settings.max_nodes {
+            self.prune();
+            if let Some(start) = self.take_free_block(length) {
+                return Some(start);
+            }
+        }
+        (self.nodes.len() + length <= self.settings.max_nodes).then_some(self.nodes.len())
+    }
+
+    // Takes the shortest free block that fits and hands the rest of it back to the free lists
+    fn take_free_block(&mut self, length: usize) -> Option<usize> {
+        let block_length = (length..self.free_blocks.len()).find(|&block_length| !self.free_blocks[block_length].is_empty())?;
+        let start = self.free_blocks[block_length].pop()?;
+        if block_length > length {
+            self.free_blocks[block_length - length].push(start + length);
+        }
+        self.free_nodes -= length;
+        Some(start)
+    }
+
+    // Cuts back the least recently visited subtrees until a share of the budget is free. A node is
+    // never visited later than its parent, so sorting by last visit with the deeper node first on
+    // ties handles every node before its ancestors. The root and the current path are kept
+    fn prune(&mut self) {
+        let mut candidates = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            for child in self.nodes[node].children() {
+                if self.nodes[child].expanded {
+                    stack.push((child, depth + 1));
+                    if self.nodes[child].last_visit != self.stamp {
+                        candidates.push((self.nodes[child].last_visit, std::cmp::Reverse(depth + 1), child));
+                    }
+                }
+            }
+        }
+        candidates.sort_unstable();
+        let target = self.settings.max_nodes / PRUNE_DIVISOR;
+        let mut freed = self.free_nodes + self.settings.max_nodes - self.nodes.len();
+        for (_, _, node) in candidates {
+            if freed >= target {
+                break;
+            }
+            freed += self.collapse(node);
+        }
+    }
+
+    // Frees the subtree below a node and turns the node back into a leaf, returns the nodes freed
+    fn collapse(&mut self, node: usize) -> usize {
+        let children = self.nodes[node].children();
+        let mut freed = children.len();
+        for child in children.clone() {
+            if self.nodes[child].expanded {
+                freed += self.collapse(child);
+            }
+        }
+        self.free_blocks[children.len()].push(children.start);
+        self.free_nodes += children.len();
+        self.recycled += children.len();
+        let node = &mut self.nodes[node];
+        node.expanded = false;
+        node.child_count = 0;
+        freed
+    }
+
+    // The moves best first by the minimax move ordering when the priors come from it
+    fn prior_order(&self, board: &board::Board, moves: u64) -> Vec<u64> {
+        match (self.settings.priors, self.network.is_some_and(|network| network.has_policy())) {
+            (PriorSource::MoveOrdering, _) | (PriorSource::Network, false) => self.orderer.order_moves(board, moves, 0, 0, 2),
+            _ => {
+                let mut squares: Vec<u64> = Vec::with_capacity(moves.count_ones() as usize);
+                let mut remaining = moves;
+                while remaining != 0 {
+                    squares.push(remaining & remaining.wrapping_neg());
+                    remaining &= remaining - 1;
+                }
+                squares
+            }
+        }
+    }
+
+    // Plays random moves up to the cutoff and scores where it ends, for the player to move at the start
+    fn rollout(&mut self, board: &board::Board) -> f32 {
+        let mut position = board.clone();
+        self.rollout_moves = [0; 2];
+        for _ in 0..self.settings.rollout_cutoff {
+            if board::Board::check_game_over(&position) {
+                break;
+            }
+            let moves = position.get_possible_moves();
+            if moves == 0 {
+                position.pass_turn();
+                continue;
+            }
+            let mut selected_move = moves;
+            for _ in 0..self.rng.gen_range(0..moves.count_ones()) {
+                selected_move &= selected_move - 1;
+            }
+            let selected_move = selected_move & selected_move.wrapping_neg();
+            self.rollout_moves[color_index(position.current_player)] |= selected_move;
+            position.place_move(&moves, selected_move);
+        }
+        let value = match board::Board::check_game_over(&position) {
+            true => endgame::final_margin(&position).signum() as f32,
+            false => heuristic_value(&position),
+        };
+        match position.current_player == board.current_player {
+            true => value,
+            false => -value,
+        }
+    }
+}
+
+fn color_index(color: constants::Color) -> usize {
+    match color {
+        constants::Color::Black => 0,
+        constants::Color::White => 1,
+    }
+}
+
+// The heuristic score of an unfinished position squashed into a value between -1 and 1
+fn heuristic_value(board: &board::Board) -> f32 {
+    (minimax::evaluate(board, 0) as f32 / HEURISTIC_VALUE_SCALE).tanh()
+}
+
+// Marsaglia and Tsang's method. Shapes below 1 are drawn with shape + 1 and scaled down
+fn sample_gamma<R: Rng>(shape: f32, rng: &mut R) -> f32 {
+    if shape < 1.0 {
+        let uniform: f32 = 1.0 - rng.gen::<f32>();
+        return sample_gamma(shape + 1.0, rng) * uniform.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        // Box-Muller gives a standard normal sample
+        let (first, second): (f32, f32) = (1.0 - rng.gen::<f32>(), rng.gen());
+        let normal = (-2.0 * first.ln()).sqrt() * (std::f32::consts::TAU * second).cos();
+        let v = (1.0 + c * normal).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let uniform: f32 = 1.0 - rng.gen::<f32>();
+        if uniform.ln() < 0.5 * normal * normal + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A position with the given number of empty squares, reached by a fixed pattern of moves
+    fn endgame_position(empties: u32) -> board::Board {
+        let mut board = board::Board::new();
+        let mut ply = 0;
+        while board.count_empty() > empties {
+            let moves = board.get_possible_moves();
+            if moves == 0 {
+                board.pass_turn();
+                continue;
+            }
+            let mut selected_move = moves;
+            for _ in 0..(ply * 5) % moves.count_ones() as usize {
+                selected_move &= selected_move - 1;
+            }
+            board.place_move(&moves, selected_move & selected_move.wrapping_neg());
+            ply += 1;
+        }
+        board
+    }
+
+    #[test]
+    fn proofs_match_the_endgame_solver() {
+        let board = endgame_position(8);
+        let margin = endgame::solve(&board, Instant::now() + Duration::from_secs(60)).unwrap();
+        for solver_empties in [0, DEFAULT_SOLVER_EMPTIES] {
+            let settings = MctsSettings { solver_empties, ..MctsSettings::default() };
+            let mut tree = Tree::new(board.clone(), settings, None, 0);
+            while tree.nodes[0].proof.is_none() {
+                tree.run(TIME_CHECK_INTERVAL);
+            }
+            assert_eq!(tree.nodes[0].proof, Some(Proof::from_margin(margin)));
+
+            // The chosen move keeps the proven result
+            let mut after = board.clone();
+            after.place_move(&board.get_possible_moves(), tree.best_move());
+            let reply = endgame::solve(&after, Instant::now() + Duration::from_secs(60)).unwrap();
+            let result = match after.current_player == board.current_player {
+                true => reply,
+                false => -reply,
+            };
+            assert_eq!(Proof::from_margin(result), Proof::from_margin(margin));
+        }
+    }
+
+    // Value of a fresh root with the given leaf evaluation
+    fn leaf_value(board: &board::Board, leaf: LeafEvaluation) -> f32 {
+        let settings = MctsSettings { leaf, rollout_cutoff: 60, solver_empties: 0, ..MctsSettings::default() };
+        Tree::new(board.clone(), settings, None, 0).expand(0)
+    }
+
+    #[test]
+    fn leaf_values_are_from_the_side_to_move() {
+        // Black holds every corner, with black to move and with white to move
+        let mut black_to_move = board::Board::new();
+        black_to_move.black |= 0x8100_0000_0000_0081;
+        let mut white_to_move = black_to_move.clone();
+        white_to_move.pass_turn();
+        // White holds every corner with white to move
+        let mut swapped = black_to_move.clone();
+        (swapped.black, swapped.white) = (black_to_move.white, black_to_move.black);
+        swapped.pass_turn();
+
+        assert!(leaf_value(&black_to_move, LeafEvaluation::Evaluate) > 0.0);
+        assert!(leaf_value(&white_to_move, LeafEvaluation::Evaluate) < 0.0);
+        assert_eq!(leaf_value(&swapped, LeafEvaluation::Evaluate), leaf_value(&black_to_move, LeafEvaluation::Evaluate));
+
+        // A rollout to the end scores the result for the player who was to move at the leaf. With one
+        // empty square left the rollout can only play the moves perfect play would
+        let board = endgame_position(1);
+        let margin = endgame::solve(&board, Instant::now() + Duration::from_secs(60)).unwrap();
+        assert_eq!(leaf_value(&board, LeafEvaluation::Rollout), margin.signum() as f32);
+    }
+
+    #[test]
+    fn timed_searches_stop_on_time_while_solving_leaves() {
+        // Leaves right below the root are too big for the solver to finish in LEAF_SOLVE_TIME
+        let board = endgame_position(24);
+        let settings = MctsSettings { solver_empties: 23, ..MctsSettings::default() };
+        let mut tree = Tree::new(board, settings, None, 0);
+        let start = Instant::now();
+        let playouts = tree.run_until(start + Duration::from_millis(30));
+        assert!(playouts > 0 && tree.solving);
+        // A batch of TIME_CHECK_INTERVAL failed solves would take over a second
+        assert!(start.elapsed() < Duration::from_millis(30) + LEAF_SOLVE_TIME * 3, "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn node_budget_bounds_the_tree() {
+        let board = board::Board::new();
+        let settings = MctsSettings { max_nodes: 500, solver_empties: 0, ..MctsSettings::default() };
+        let mut tree = Tree::new(board.clone(), settings, None, 0);
+        for _ in 0..50 {
+            tree.run(TIME_CHECK_INTERVAL);
+            assert!(tree.nodes.len() <= 500);
+            assert!(tree.size() <= 500);
+        }
+        assert!(tree.recycled() > 0);
+        assert_eq!(tree.memory_use(), tree.nodes.capacity() * std::mem::size_of::<Node>());
+        assert_ne!(board.get_possible_moves() & tree.best_move(), 0);
+        // The root keeps its children and their statistics
+        let visits: u32 = tree.nodes[0].children().map(|child| tree.nodes[child].visits).sum();
+        assert_eq!(visits + 1, tree.nodes[0].visits);
+    }
+
+    #[test]
+    fn rave_share_falls_with_visits() {
+        for schedule in [RaveSchedule::Equivalence(300.0), RaveSchedule::MinimumError(0.1)] {
+            assert_eq!(schedule.beta(0, 10), 1.0);
+            let shares: Vec<f32> = [1, 10, 100, 1000].iter().map(|&visits| schedule.beta(visits, 2 * visits)).collect();
+            assert!(shares.windows(2).all(|pair| pair[1] < pair[0]));
+        }
+        // The equivalence constant is where both values weigh the same
+        assert!((RaveSchedule::Equivalence(300.0).beta(300, 300) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,160 @@
+//Modules
+use crate::{board, constants};
+
+// Moves are searched in descending order of these scores
+const TT_MOVE_SCORE: i64 = 1 << 40;
+const KILLER_SCORE: i64 = 1 << 36;
+// Every reply the move leaves the opponent costs this much
+const MOBILITY_WEIGHT: i64 = 1 << 24;
+// History scores are capped so they only break ties between moves of equal mobility
+const HISTORY_CAP: u32 = (1 << 20) - 1;
+const HISTORY_WEIGHT: i64 = 1 << 3;
+// Counting replies costs a move generation per move, so it is skipped close to the leaves
+const MOBILITY_ORDERING_MIN_DEPTH: i32 = 2;
+// Killer moves remembered per ply
+const KILLER_SLOTS: usize = 2;
+
+// Fallback when nothing else tells the moves apart: corners first, X-squares last. One table per
+// board size, indexed by constants::size_index
+const SQUARE_PRIORITIES: [[i64; 64]; 3] = [square_priorities(4), square_priorities(6), square_priorities(8)];
+
+/// Counts how often the first move searched was the one that caused a cutoff
+#[derive(Clone, Copy, Default)]
+pub struct OrderingStats {
+    pub cutoffs: u64,
+    pub first_move_cutoffs: u64
+}
+
+impl OrderingStats {
+    /// Returns the share of cutoffs caused by the first move, as a percentage
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        match self.cutoffs {
+            0 => 0.0,
+            cutoffs => 100.0 * self.first_move_cutoffs as f64 / cutoffs as f64,
+        }
+    }
+}
+
+/// Orders moves for alpha-beta, learning from the cutoffs found during the search
+pub struct MoveOrderer {
+    killers: Vec<[u64; KILLER_SLOTS]>,
+    history: [[u32; 64]; 2],
+    pub stats: OrderingStats
+}
+
+impl Default for MoveOrderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveOrderer {
+    pub fn new() -> Self {
+        Self { killers: Vec::new(), history: [[0; 64]; 2], stats: OrderingStats::default() }
+    }
+
+    ///Returns the moves in the order they should be searched.
+    /// The move from the transposition table goes first, then the killer moves of this ply,
+    /// then the rest by fewest opponent replies, history score and square priority.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'state' - The position the moves are played from
+    ///  * 'moves' - The bitboard of legal moves
+    ///  * 'tt_move' - The best move stored in the transposition table, or 0
+    ///  * 'ply' - How far the position is from the root of the search
+    ///  * 'depth' - How many plies are left to search below the position
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// for current_move in orderer.order_moves(&state, moves, tt_move, ply, depth) {
+    ///     // search current_move
+    /// }
+    /// ```
+    pub fn order_moves(&self, state: &board::Board, moves: u64, tt_move: u64, ply: usize, depth: i32) -> Vec<u64> {
+        let color = color_index(state.current_player);
+        let killers = self.killers.get(ply).copied().unwrap_or([0; KILLER_SLOTS]);
+        let square_priority = &SQUARE_PRIORITIES[constants::size_index(state.size())];
+        let mut scored: Vec<(i64, u64)> = Vec::with_capacity(moves.count_ones() as usize);
+        let mut remaining = moves;
+        while remaining != 0 {
+            let current_move = remaining & remaining.wrapping_neg();
+            remaining ^= current_move;
+            let square = current_move.trailing_zeros() as usize;
+
+            let score = if current_move == tt_move {
+                TT_MOVE_SCORE
+            } else if let Some(slot) = killers.iter().position(|&killer| killer == current_move) {
+                KILLER_SCORE - slot as i64
+            } else {
+                let mut score = self.history[color][square].min(HISTORY_CAP) as i64 * HISTORY_WEIGHT + square_priority[square];
+                if depth >= MOBILITY_ORDERING_MIN_DEPTH {
+                    let mut new_state = state.clone();
+                    new_state.place_move(&moves, current_move);
+                    score -= new_state.get_possible_moves().count_ones() as i64 * MOBILITY_WEIGHT;
+                }
+                score
+            };
+            scored.push((score, current_move));
+        }
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        scored.into_iter().map(|(_, current_move)| current_move).collect()
+    }
+
+    ///Records a move that caused a beta cutoff so it is tried earlier in similar positions
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'color' - The player who played the move
+    ///  * 'cutoff_move' - The move that caused the cutoff
+    ///  * 'ply' - How far the position is from the root of the search
+    ///  * 'depth' - How many plies were left to search below the position
+    ///  * 'move_number' - How many moves were searched before it
+    pub fn record_cutoff(&mut self, color: constants::Color, cutoff_move: u64, ply: usize, depth: i32, move_number: usize) {
+        self.stats.cutoffs += 1;
+        if move_number == 0 {
+            self.stats.first_move_cutoffs += 1;
+        }
+
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [0; KILLER_SLOTS]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != cutoff_move {
+            killers[1] = killers[0];
+            killers[0] = cutoff_move;
+        }
+
+        let square = cutoff_move.trailing_zeros() as usize;
+        let history = &mut self.history[color_index(color)][square];
+        *history = history.saturating_add((depth * depth) as u32);
+    }
+}
+
+fn color_index(color: constants::Color) -> usize {
+    match color {
+        constants::Color::Black => 0,
+        constants::Color::White => 1,
+    }
+}
+
+// Priorities of the squares of one board size, by how far they are from the edges
+const fn square_priorities(size: usize) -> [i64; 64] {
+    let mut priorities = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        priorities[index] = match constants::edge_distances(index, size) {
+            Some((0, 0)) => 7,
+            Some((0, 1)) => 1,
+            Some((1, 1)) | None => 0,
+            Some((0, 2)) => 6,
+            Some((0, _)) => 5,
+            Some((1, 2)) => 2,
+            Some((1, _)) => 3,
+            Some(_) => 4,
+        };
+        index += 1;
+    }
+    priorities
+}
@@ -0,0 +1,126 @@
+//Imports
+use std::time::Instant;
+
+//Modules
+use crate::board;
+use crate::ai_logic::{EngineOptions, SearchAlgorithm};
+use crate::ai_logic::minimax::{self, Search};
+use crate::ai_logic::transposition::TranspositionTable;
+
+// Positions in the comparison set, and the plies played to reach the first and last of them
+const COMPARISON_POSITIONS: usize = 12;
+const COMPARISON_FIRST_PLY: usize = 8;
+const COMPARISON_PLY_STEP: usize = 3;
+
+///Searches the board to a fixed depth with MTD(f): a sequence of null window searches that
+/// close in on the score from a first guess. The transposition table keeps the bounds proven
+/// by every pass, so each pass only searches what the previous ones left open.
+/// Returns the score with the principal variation, or None if the search ran out of time.
+///
+///  # Arguments
+///
+///  * 'search' - The search to run in, with its table and clock
+///  * 'board' - The position to search
+///  * 'depth' - How many plies deep to search
+///  * 'first_guess' - Where to start, usually the score of the previous iteration
+///
+///  # Examples
+///
+/// ```
+/// let (score, line) = mtdf::mtdf(&mut search, &board, 10, previous_score)?;
+/// ```
+pub(super) fn mtdf(search: &mut Search<'_>, board: &board::Board, depth: i32, first_guess: i32) -> Option<(i32, Vec<u64>)> {
+    let mut score = first_guess;
+    let (mut lower, mut upper) = (-minimax::INFINITY, minimax::INFINITY);
+    let mut line: Vec<u64> = Vec::new();
+    while lower < upper {
+        let beta = match score == lower {
+            true => score + 1,
+            false => score,
+        };
+        let mut pass_line: Vec<u64> = Vec::new();
+        score = search.pvs(board.clone(), depth, 0, beta - 1, beta, &mut pass_line);
+        if search.timed_out() {
+            return None;
+        }
+        // A pass that fails high found a move reaching the new lower bound
+        if score < beta {
+            upper = score;
+        } else {
+            lower = score;
+            line = pass_line;
+        }
+    }
+    Some((score, line))
+}
+
+///Searches a fixed set of positions to the same depth with PVS and with MTD(f) and prints the
+/// nodes, time and score of each, so the two drivers can be compared. Multi-ProbCut is off
+/// so both return the exact score of the depth.
+///
+///  # Arguments
+///
+///  * 'depth' - How many plies deep to search every position
+///
+///  # Examples
+///
+/// ```
+/// mtdf::run_comparison(8);
+/// ```
+pub fn run_comparison(depth: i32) {
+    println!("PVS against MTD(f) at depth {} on {} positions", depth, COMPARISON_POSITIONS);
+    println!("position\tPVS nodes\tMTD(f) nodes\tPVS time\tMTD(f) time\tscores");
+    let mut totals = [0u64; 2];
+    let mut table = TranspositionTable::new();
+    for (index, position) in comparison_positions().iter().enumerate() {
+        let mut results: Vec<(u64, f64, i32)> = Vec::new();
+        for algorithm in [SearchAlgorithm::Pvs, SearchAlgorithm::Mtdf] {
+            let options = EngineOptions { algorithm, selectivity: 0, ..EngineOptions::default() };
+            let mut search = Search::unlimited(&options, &mut table);
+            let start_time = Instant::now();
+            let score = match algorithm {
+                SearchAlgorithm::Pvs => search.pvs(position.clone(), depth, 0, -minimax::INFINITY, minimax::INFINITY, &mut Vec::new()),
+                SearchAlgorithm::Mtdf => mtdf(&mut search, position, depth, 0).map_or(0, |(score, _)| score),
+            };
+            results.push((search.nodes(), start_time.elapsed().as_secs_f64(), score));
+        }
+        totals[0] += results[0].0;
+        totals[1] += results[1].0;
+        let scores = match results[0].2 == results[1].2 {
+            true => format!("{}", results[0].2),
+            false => format!("{} / {} DIFFERENT", results[0].2, results[1].2),
+        };
+        println!("{}\t\t{}\t\t{}\t\t{:.3}s\t\t{:.3}s\t\t{}", index + 1, results[0].0, results[1].0, results[0].1, results[1].1, scores);
+    }
+    println!("total\t\t{}\t\t{}\t\tMTD(f) searched {:.1}% of the PVS nodes", totals[0], totals[1], 100.0 * totals[1] as f64 / totals[0].max(1) as f64);
+}
+
+// Positions from the opening to the late midgame, reached by a fixed pattern of moves so every
+// run compares the same set
+fn comparison_positions() -> Vec<board::Board> {
+    (0..COMPARISON_POSITIONS)
+        .filter_map(|index| {
+            let mut board = board::Board::new();
+            for ply in 0..COMPARISON_FIRST_PLY + index * COMPARISON_PLY_STEP {
+                let possible_moves = board.get_possible_moves();
+                if board::Board::check_game_over(&board) {
+                    return None;
+                }
+                if possible_moves == 0 {
+                    board.pass_turn();
+                    continue;
+                }
+                let mut selected_move = possible_moves;
+                for _ in 0..(index * 7 + ply * 3) % possible_moves.count_ones() as usize {
+                    selected_move &= selected_move - 1;
+                }
+                board.place_move(&possible_moves, selected_move & selected_move.wrapping_neg());
+            }
+            // The searches need a move to make
+            match board.get_possible_moves() {
+                0 => None,
+                _ => Some(board),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,521 @@
+//Imports
+use std::fs;
+use std::sync::OnceLock;
+use rand::Rng;
+
+//Modules
+use crate::{board, constants};
+
+// Every weights file starts with these bytes, followed by the format version
+const MAGIC: &[u8; 4] = b"RTNN";
+const VERSION: u32 = 1;
+// One input per square for the discs of the player to move, and one for the opponent's
+const BOARD_INPUTS: usize = 128;
+// Optional inputs after the board, turned on by bits of the feature flags in the file header
+pub const FEATURE_MOBILITY: u32 = 1;
+pub const FEATURE_FRONTIER: u32 = 1 << 1;
+pub const FEATURE_EMPTIES: u32 = 1 << 2;
+const KNOWN_FEATURES: u32 = FEATURE_MOBILITY | FEATURE_FRONTIER | FEATURE_EMPTIES;
+// Brings the counts used as features into roughly the same range as the disc inputs
+const COUNT_SCALE: f32 = 1.0 / 16.0;
+// A network with a policy head has the value output followed by one move logit per square
+const POLICY_OUTPUTS: usize = 65;
+
+static NETWORK: OnceLock<Network> = OnceLock::new();
+
+/// A fully connected layer. The weights are stored input by input, so the weights of one input
+/// to every output are next to each other and inputs that are zero can be skipped
+#[derive(Clone)]
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    weights: Vec<f32>,
+    biases: Vec<f32>
+}
+
+impl Layer {
+    fn forward(&self, input: &[f32], output: &mut Vec<f32>) {
+        output.clear();
+        output.extend_from_slice(&self.biases);
+        for (index, &value) in input.iter().enumerate() {
+            if value == 0.0 {
+                continue;
+            }
+            let weights = &self.weights[index * self.outputs..(index + 1) * self.outputs];
+            for (sum, weight) in output.iter_mut().zip(weights) {
+                *sum += value * weight;
+            }
+        }
+    }
+}
+
+/// A small feed-forward network that estimates the result of a position for the player to move.
+/// Hidden layers use ReLU and the value output goes through tanh, so 1 is a certain win and -1 a certain loss.
+/// A network can also have a policy head, one logit per square for how likely each move is to be best.
+#[derive(Clone)]
+pub struct Network {
+    features: u32,
+    layers: Vec<Layer>
+}
+
+impl Network {
+    ///Creates a network with random weights, scaled so the activations keep their size from layer to layer
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'features' - The optional inputs to use, FEATURE_* flags
+    ///  * 'hidden' - The size of every hidden layer
+    ///  * 'policy' - Whether to add a policy head to the value output
+    ///  * 'rng' - The random number generator to draw the weights from
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let network = Network::random(FEATURE_MOBILITY, &[64, 32], true, &mut rand::thread_rng());
+    /// ```
+    pub fn random<R: Rng>(features: u32, hidden: &[usize], policy: bool, rng: &mut R) -> Self {
+        let mut sizes = vec![input_size(features)];
+        sizes.extend_from_slice(hidden);
+        sizes.push(match policy {
+            true => POLICY_OUTPUTS,
+            false => 1,
+        });
+        let layers = sizes.windows(2)
+            .map(|pair| {
+                let (inputs, outputs) = (pair[0], pair[1]);
+                let range = (6.0 / inputs as f32).sqrt();
+                let weights = (0..inputs * outputs).map(|_| rng.gen_range(-range..range)).collect();
+                Layer { inputs, outputs, weights, biases: vec![0.0; outputs] }
+            })
+            .collect();
+        Self { features, layers }
+    }
+
+    ///Reads a network from the bytes of a weights file. All numbers are little endian: the magic
+    /// bytes "RTNN", the version, the feature flags and the layer count as u32, then for every layer
+    /// its input and output count as u32 followed by its weights input by input and its biases as f32.
+    /// The last layer has a single value output, or the value followed by 64 policy logits.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'bytes' - The contents of the weights file
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let network = Network::from_bytes(&fs::read("network.bin")?)?;
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(String::from("not a network weights file"));
+        }
+        let version = reader.read_u32()?;
+        if version != VERSION {
+            return Err(format!("unsupported weights file version {}", version));
+        }
+        let features = reader.read_u32()?;
+        if features & !KNOWN_FEATURES != 0 {
+            return Err(format!("unknown feature flags {:#x}", features));
+        }
+        let layer_count = reader.read_u32()? as usize;
+        let mut layers: Vec<Layer> = Vec::with_capacity(layer_count);
+        let mut expected_inputs = input_size(features);
+        for _ in 0..layer_count {
+            let inputs = reader.read_u32()? as usize;
+            let outputs = reader.read_u32()? as usize;
+            if inputs != expected_inputs || outputs == 0 {
+                return Err(format!("layer {} has {} inputs, expected {}", layers.len() + 1, inputs, expected_inputs));
+            }
+            let weights = reader.read_f32s(inputs * outputs)?;
+            let biases = reader.read_f32s(outputs)?;
+            layers.push(Layer { inputs, outputs, weights, biases });
+            expected_inputs = outputs;
+        }
+        if layers.is_empty() || (expected_inputs != 1 && expected_inputs != POLICY_OUTPUTS) {
+            return Err(format!("the last layer must have 1 output or {} with a policy head", POLICY_OUTPUTS));
+        }
+        if reader.position != bytes.len() {
+            return Err(String::from("unexpected data after the last layer"));
+        }
+        Ok(Self { features, layers })
+    }
+
+    ///Returns the network in the weights file format read by from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        for value in [VERSION, self.features, self.layers.len() as u32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for layer in &self.layers {
+            bytes.extend_from_slice(&(layer.inputs as u32).to_le_bytes());
+            bytes.extend_from_slice(&(layer.outputs as u32).to_le_bytes());
+            for value in layer.weights.iter().chain(&layer.biases) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    ///Writes the network to a weights file
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'path' - The file to write
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|error| format!("could not write {}: {}", path, error))
+    }
+
+    ///Returns true if the network has a policy head
+    pub fn has_policy(&self) -> bool {
+        self.layers.last().is_some_and(|layer| layer.outputs == POLICY_OUTPUTS)
+    }
+
+    ///Returns the network's estimate of the result for the player to move, between -1 and 1
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position to evaluate
+    pub fn evaluate(&self, board: &board::Board) -> f32 {
+        self.outputs(board)[0].tanh()
+    }
+
+    ///Returns the estimate of the result for the player to move with the policy logit of every
+    /// square, or None for the policy if the network has no policy head
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position to evaluate
+    pub fn predict(&self, board: &board::Board) -> (f32, Option<Vec<f32>>) {
+        let outputs = self.outputs(board);
+        let policy = match outputs.len() {
+            POLICY_OUTPUTS => Some(outputs[1..].to_vec()),
+            _ => None,
+        };
+        (outputs[0].tanh(), policy)
+    }
+
+    ///Takes one gradient step on a batch of samples, with the squared error of the value and the
+    /// cross entropy of the policy as the loss. Returns the average value and policy loss before the step.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'batch' - The samples to learn from
+    ///  * 'optimizer' - The learning rate and momentum of this network
+    pub fn train_batch(&mut self, batch: &[Sample], optimizer: &mut Optimizer) -> (f32, f32) {
+        let mut gradients: Vec<(Vec<f32>, Vec<f32>)> = self.layers.iter()
+            .map(|layer| (vec![0.0; layer.weights.len()], vec![0.0; layer.biases.len()]))
+            .collect();
+        let (mut value_loss, mut policy_loss) = (0.0, 0.0);
+        for sample in batch {
+            let board = board::Board { black: sample.player, white: sample.opponent, current_player: constants::Color::Black, squares: constants::FULL, variant: constants::Variant::Standard };
+            // Inputs of every layer, then the raw outputs of the last one
+            let mut activations = vec![self.inputs(&board)];
+            for (index, layer) in self.layers.iter().enumerate() {
+                let mut output = Vec::with_capacity(layer.outputs);
+                layer.forward(&activations[index], &mut output);
+                if index + 1 < self.layers.len() {
+                    output.iter_mut().for_each(|value| *value = value.max(0.0));
+                }
+                activations.push(output);
+            }
+
+            let outputs = &activations[self.layers.len()];
+            let mut delta = vec![0.0; outputs.len()];
+            let value = outputs[0].tanh();
+            value_loss += (value - sample.value).powi(2);
+            delta[0] = 2.0 * (value - sample.value) * (1.0 - value * value);
+            if outputs.len() == POLICY_OUTPUTS {
+                let probabilities = softmax(&outputs[1..]);
+                for square in 0..64 {
+                    if sample.policy[square] > 0.0 {
+                        policy_loss -= sample.policy[square] * probabilities[square].max(1e-9).ln();
+                    }
+                    delta[square + 1] = probabilities[square] - sample.policy[square];
+                }
+            }
+
+            for (index, layer) in self.layers.iter().enumerate().rev() {
+                let input = &activations[index];
+                let (weight_gradient, bias_gradient) = &mut gradients[index];
+                for (output, &change) in delta.iter().enumerate() {
+                    bias_gradient[output] += change;
+                }
+                // ReLU passes the gradient only where it was active, so inputs that are zero
+                // neither get a gradient nor pass one on
+                let mut previous = vec![0.0; layer.inputs];
+                for (input_index, &value) in input.iter().enumerate() {
+                    if value == 0.0 {
+                        continue;
+                    }
+                    let weights = &layer.weights[input_index * layer.outputs..(input_index + 1) * layer.outputs];
+                    let gradient = &mut weight_gradient[input_index * layer.outputs..(input_index + 1) * layer.outputs];
+                    let mut sum = 0.0;
+                    for ((gradient, weight), &change) in gradient.iter_mut().zip(weights).zip(&delta) {
+                        *gradient += value * change;
+                        sum += weight * change;
+                    }
+                    previous[input_index] = sum;
+                }
+                delta = previous;
+            }
+        }
+
+        let scale = 1.0 / batch.len().max(1) as f32;
+        for ((layer, gradient), velocity) in self.layers.iter_mut().zip(&gradients).zip(optimizer.velocity.iter_mut()) {
+            let parameters = layer.weights.iter_mut().chain(layer.biases.iter_mut());
+            let gradient = gradient.0.iter().chain(&gradient.1);
+            let velocity = velocity.0.iter_mut().chain(velocity.1.iter_mut());
+            for ((parameter, &gradient), velocity) in parameters.zip(gradient).zip(velocity) {
+                *velocity = optimizer.momentum * *velocity - optimizer.learning_rate * (gradient * scale + optimizer.weight_decay * *parameter);
+                *parameter += *velocity;
+            }
+        }
+        (value_loss * scale, policy_loss * scale)
+    }
+
+    // Raw outputs of the last layer
+    fn outputs(&self, board: &board::Board) -> Vec<f32> {
+        let mut activations = self.inputs(board);
+        let mut next: Vec<f32> = Vec::with_capacity(self.layers.iter().map(|layer| layer.outputs).max().unwrap_or(1));
+        for (index, layer) in self.layers.iter().enumerate() {
+            debug_assert_eq!(activations.len(), layer.inputs);
+            layer.forward(&activations, &mut next);
+            if index + 1 < self.layers.len() {
+                next.iter_mut().for_each(|value| *value = value.max(0.0));
+            }
+            std::mem::swap(&mut activations, &mut next);
+        }
+        activations
+    }
+
+    // Board inputs are 0 or 1, so most of them are skipped by the first layer
+    fn inputs(&self, board: &board::Board) -> Vec<f32> {
+        let player_color = board.current_player;
+        let opponent_color = player_color.opponent();
+        let player = board.get_color_bitboard(player_color);
+        let opponent = board.get_color_bitboard(opponent_color);
+        let mut inputs = vec![0.0; input_size(self.features)];
+        for square in 0..64 {
+            if player & (1 << square) != 0 {
+                inputs[square] = 1.0;
+            } else if opponent & (1 << square) != 0 {
+                inputs[64 + square] = 1.0;
+            }
+        }
+        let mut next = BOARD_INPUTS;
+        if self.features & FEATURE_MOBILITY != 0 {
+            inputs[next] = board.count_mobility(player_color) as f32 * COUNT_SCALE;
+            inputs[next + 1] = board.count_mobility(opponent_color) as f32 * COUNT_SCALE;
+            next += 2;
+        }
+        if self.features & FEATURE_FRONTIER != 0 {
+            inputs[next] = board.count_frontier(player_color) as f32 * COUNT_SCALE;
+            inputs[next + 1] = board.count_frontier(opponent_color) as f32 * COUNT_SCALE;
+            next += 2;
+        }
+        if self.features & FEATURE_EMPTIES != 0 {
+            inputs[next] = board.count_empty() as f32 * COUNT_SCALE;
+        }
+        inputs
+    }
+}
+
+/// A training target: a position with the player to move first, how often each move was chosen by
+/// the tree search, and the final result of the game for the player to move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    pub player: u64,
+    pub opponent: u64,
+    pub policy: [f32; 64],
+    pub value: f32
+}
+
+/// Stochastic gradient descent with momentum and weight decay, keeping a velocity per weight
+pub struct Optimizer {
+    learning_rate: f32,
+    momentum: f32,
+    weight_decay: f32,
+    velocity: Vec<(Vec<f32>, Vec<f32>)>
+}
+
+impl Optimizer {
+    ///Creates an optimizer for the network with every velocity at zero
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'network' - The network the optimizer will train
+    ///  * 'learning_rate' - How far every step moves the weights
+    ///  * 'momentum' - How much of the previous step carries over into the next one
+    ///  * 'weight_decay' - How strongly the weights are pulled towards zero
+    pub fn new(network: &Network, learning_rate: f32, momentum: f32, weight_decay: f32) -> Self {
+        let velocity = network.layers.iter()
+            .map(|layer| (vec![0.0; layer.weights.len()], vec![0.0; layer.biases.len()]))
+            .collect();
+        Self { learning_rate, momentum, weight_decay, velocity }
+    }
+}
+
+///Returns the probabilities the logits stand for
+pub fn softmax(logits: &[f32]) -> Vec<f32> {
+    let highest = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exponentials: Vec<f32> = logits.iter().map(|logit| (logit - highest).exp()).collect();
+    let total: f32 = exponentials.iter().sum();
+    exponentials.iter().map(|value| value / total).collect()
+}
+
+///Loads the network used by the neural evaluator from a weights file. Only the first network
+/// loaded is kept for the rest of the run.
+///
+///  # Arguments
+///
+///  * 'path' - The weights file to read
+///
+///  # Examples
+///
+/// ```
+/// neural_network::load("network.bin")?;
+/// ```
+pub fn load(path: &str) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|error| format!("could not read {}: {}", path, error))?;
+    let network = Network::from_bytes(&bytes)?;
+    let _ = NETWORK.set(network);
+    Ok(())
+}
+
+///Returns the loaded network, or None if no weights file has been loaded
+pub fn network() -> Option<&'static Network> {
+    NETWORK.get()
+}
+
+///Returns the loaded network if it can score the board, or None if no weights file has been loaded
+/// or the board is not one the networks are trained on
+///
+///  # Arguments
+///
+///  * 'board' - The position the network would score
+pub fn network_for(board: &board::Board) -> Option<&'static Network> {
+    network().filter(|_| trained_on(board))
+}
+
+// The networks are trained on standard games on the 8x8 board only, smaller boards would reach
+// them padded out to 64 squares
+fn trained_on(board: &board::Board) -> bool {
+    board.variant == constants::Variant::Standard && board.size() == constants::BOARD_SIZE
+}
+
+// Number of inputs a network with the given features takes
+fn input_size(features: u32) -> usize {
+    let mut size = BOARD_INPUTS;
+    if features & FEATURE_MOBILITY != 0 {
+        size += 2;
+    }
+    if features & FEATURE_FRONTIER != 0 {
+        size += 2;
+    }
+    if features & FEATURE_EMPTIES != 0 {
+        size += 1;
+    }
+    size
+}
+
+/// Reads little endian numbers from the weights file
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| String::from("the weights file ends early"))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_f32s(&mut self, count: usize) -> Result<Vec<f32>, String> {
+        let bytes = self.take(count.checked_mul(4).ok_or_else(|| String::from("layer too large"))?)?;
+        Ok(bytes.chunks_exact(4).map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_f32s(bytes: &mut Vec<u8>, values: &[f32]) {
+        values.iter().for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+    }
+
+    // One hidden unit counting the discs of the player to move, and an output of half of it
+    fn disc_counting_network() -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        push_u32(&mut bytes, VERSION);
+        push_u32(&mut bytes, FEATURE_MOBILITY);
+        push_u32(&mut bytes, 2);
+        push_u32(&mut bytes, 130);
+        push_u32(&mut bytes, 1);
+        let weights: Vec<f32> = (0..130).map(|input| if input < 64 { 1.0 } else { 0.0 }).collect();
+        push_f32s(&mut bytes, &weights);
+        push_f32s(&mut bytes, &[0.0]);
+        push_u32(&mut bytes, 1);
+        push_u32(&mut bytes, 1);
+        push_f32s(&mut bytes, &[0.5, 0.0]);
+        bytes
+    }
+
+    #[test]
+    fn network_reads_weights_and_evaluates() {
+        let network = Network::from_bytes(&disc_counting_network()).unwrap();
+        let board = board::Board::new();
+        assert!((network.evaluate(&board) - 1.0f32.tanh()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn only_standard_eight_by_eight_games_reach_the_network() {
+        assert!(trained_on(&board::Board::new()));
+        assert!(!trained_on(&board::Board::new().with_variant(constants::Variant::Anti)));
+        for size in [4, 6] {
+            assert!(!trained_on(&board::Board::with_size(size).unwrap()));
+        }
+    }
+
+    #[test]
+    fn training_fits_a_sample_and_survives_saving() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut network = Network::random(FEATURE_MOBILITY, &[16], true, &mut rng);
+        let board = board::Board::new();
+        let mut policy = [0.0; 64];
+        policy[19] = 1.0;
+        let sample = Sample { player: board.black, opponent: board.white, policy, value: 0.5 };
+        let mut optimizer = Optimizer::new(&network, 0.01, 0.9, 0.0);
+        let (first_value_loss, first_policy_loss) = network.train_batch(&[sample], &mut optimizer);
+        for _ in 0..200 {
+            network.train_batch(&[sample], &mut optimizer);
+        }
+        let (value_loss, policy_loss) = network.train_batch(&[sample], &mut optimizer);
+        assert!(value_loss < first_value_loss && value_loss < 0.01);
+        assert!(policy_loss < first_policy_loss && policy_loss < 0.1);
+
+        let loaded = Network::from_bytes(&network.to_bytes()).unwrap();
+        assert_eq!(loaded.predict(&board), network.predict(&board));
+    }
+
+    #[test]
+    fn truncated_weights_are_rejected() {
+        let bytes = disc_counting_network();
+        assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Network::from_bytes(&bytes[4..]).is_err());
+    }
+}
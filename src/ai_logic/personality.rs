@@ -0,0 +1,145 @@
+//Imports
+use std::fmt;
+
+//Modules
+use crate::constants;
+
+// Squares of each quadrant of every board size, for region parity, indexed by constants::size_index
+const QUADRANTS: [[u64; 4]; 3] = [quadrants(4), quadrants(6), quadrants(8)];
+
+/// Weights of the terms of the minimax evaluation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EvaluationWeights {
+    // Worth of a disc in the opening, the midgame (ply 10 to 30) and the endgame
+    pub discs: [i32; 3],
+    pub mobility: i32,
+    pub frontier: i32,
+    // Scale of the positional table, in percent
+    pub position: i32,
+    pub corner: i32,
+    pub edge: i32,
+    pub stability: i32,
+    // Worth of every quadrant with an odd number of empty squares, where the player to move gets the last move
+    pub parity: i32
+}
+
+impl EvaluationWeights {
+    ///Returns the parity term of a position for the player to move
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'empty' - The empty squares of the board
+    ///  * 'size' - The number of rows and columns of the board
+    pub fn parity_score(&self, empty: u64, size: usize) -> i32 {
+        QUADRANTS[constants::size_index(size)].iter().filter(|&&quadrant| (empty & quadrant).count_ones() % 2 == 1).count() as i32 * self.parity
+    }
+}
+
+// The four quadrants of a board, split in half across the rows and along them
+const fn quadrants(size: usize) -> [u64; 4] {
+    let mut quadrants = [0; 4];
+    let half = size / 2;
+    let mut row = 0;
+    while row < size {
+        let mut column = 0;
+        while column < size {
+            let quadrant = (row >= half) as usize * 2 + (column >= half) as usize;
+            quadrants[quadrant] |= 1 << (row * constants::BOARD_SIZE + column);
+            column += 1;
+        }
+        row += 1;
+    }
+    quadrants
+}
+
+/// A playing style of the minimax engine, with its evaluation weights and search settings
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Personality {
+    // The standard evaluation
+    Balanced,
+    // Keeps its own moves open and the opponent's few
+    MobilityFocused,
+    // Takes as many discs as it can
+    DiscGreedy,
+    // Goes for edges and corners
+    EdgeGrabber,
+    // Plays for the last move in every region
+    ParityPlayer
+}
+
+/// Every personality, in the order the help text lists them
+pub const PERSONALITIES: [Personality; 5] = [
+    Personality::Balanced,
+    Personality::MobilityFocused,
+    Personality::DiscGreedy,
+    Personality::EdgeGrabber,
+    Personality::ParityPlayer,
+];
+
+impl Personality {
+    ///Returns the personality with the given name, as printed by Display
+    pub fn from_name(name: &str) -> Option<Self> {
+        PERSONALITIES.iter().copied().find(|personality| personality.to_string() == name.to_lowercase())
+    }
+
+    ///Returns the evaluation weights of the personality
+    pub fn weights(self) -> EvaluationWeights {
+        let balanced = EvaluationWeights { discs: [5, 12, 25], mobility: 10, frontier: 5, position: 100, corner: 25, edge: 5, stability: 20, parity: 0 };
+        match self {
+            Personality::Balanced => balanced,
+            Personality::MobilityFocused => EvaluationWeights { discs: [0, 4, 20], mobility: 30, frontier: 15, ..balanced },
+            Personality::DiscGreedy => EvaluationWeights { discs: [30, 30, 40], mobility: 2, frontier: 0, position: 30, ..balanced },
+            Personality::EdgeGrabber => EvaluationWeights { corner: 40, edge: 30, ..balanced },
+            Personality::ParityPlayer => EvaluationWeights { parity: 30, ..balanced },
+        }
+    }
+
+    ///Returns the Multi-ProbCut selectivity level the personality searches with
+    pub fn selectivity(self) -> usize {
+        match self {
+            Personality::Balanced | Personality::MobilityFocused => constants::DEFAULT_SELECTIVITY,
+            // The ProbCut regressions are fitted to the balanced evaluation, so far-off weights cut less
+            Personality::EdgeGrabber => 1,
+            // Disc counts and parity swing from one ply to the next, so shallow searches predict them badly
+            Personality::DiscGreedy | Personality::ParityPlayer => 0,
+        }
+    }
+}
+
+impl fmt::Display for Personality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Personality::Balanced => write!(f, "balanced"),
+            Personality::MobilityFocused => write!(f, "mobility-focused"),
+            Personality::DiscGreedy => write!(f, "disc-greedy"),
+            Personality::EdgeGrabber => write!(f, "edge-grabber"),
+            Personality::ParityPlayer => write!(f, "parity-player"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for personality in PERSONALITIES {
+            assert_eq!(Personality::from_name(&personality.to_string()), Some(personality));
+        }
+        assert_eq!(Personality::from_name("Edge-Grabber"), Some(Personality::EdgeGrabber));
+        assert_eq!(Personality::from_name("aggressive"), None);
+    }
+
+    #[test]
+    fn parity_counts_odd_quadrants() {
+        let weights = Personality::ParityPlayer.weights();
+        // One empty square in the a1 quadrant and two in the h8 quadrant
+        assert_eq!(weights.parity_score(1 | 1 << 63 | 1 << 62, 8), weights.parity);
+        assert_eq!(weights.parity_score(constants::FULL, 8), 0);
+        assert_eq!(Personality::Balanced.weights().parity_score(1, 8), 0);
+        // a1 and d4 share a quadrant on the 8x8 board but not on the 6x6 board
+        assert_eq!(weights.parity_score(1 | 1 << 27, 8), 0);
+        assert_eq!(weights.parity_score(1 | 1 << 27, 6), 2 * weights.parity);
+    }
+}
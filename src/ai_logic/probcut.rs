@@ -0,0 +1,312 @@
+//Imports
+use std::fs;
+use std::sync::OnceLock;
+use std::time::Instant;
+use rand::Rng;
+
+//Modules
+use crate::{board, constants, seed};
+use crate::ai_logic::minimax;
+
+// Cut thresholds in standard deviations of the regression error, indexed by selectivity level.
+// Level 0 turns Multi-ProbCut off
+const SELECTIVITY_THRESHOLDS: [f64; 5] = [f64::INFINITY, 2.6, 2.0, 1.5, 1.0];
+// Shallowest and deepest searches Multi-ProbCut is tried at. Deeper searches use the checks
+// calibrated for the deepest one with the same parity
+pub const MIN_DEPTH: i32 = 3;
+const MAX_CALIBRATED_DEPTH: i32 = 14;
+// Game stages the regressions are fitted separately for, by discs on the board
+const STAGE_COUNT: usize = 4;
+const DISCS_PER_STAGE: u32 = 16;
+// Shallow searches tried per deep search
+const CHECKS_PER_DEPTH: usize = 2;
+// Used until calibrate has been run, the error grows with the depth difference
+const DEFAULT_SIGMA: f64 = 40.0;
+const DEFAULT_SIGMA_PER_PLY: f64 = 15.0;
+// Random moves played at the start of every calibration game
+const OPENING_RANDOM_MOVES: usize = 10;
+// A regression needs at least this many samples to replace the default
+const MIN_SAMPLES: usize = 30;
+
+static TABLE: OnceLock<ProbCutTable> = OnceLock::new();
+
+/// A shallow search that predicts a deeper one as deep_score = slope * shallow_score + intercept,
+/// with sigma the standard deviation of the prediction error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbCutCheck {
+    pub shallow_depth: i32,
+    pub slope: f64,
+    pub intercept: f64,
+    pub sigma: f64
+}
+
+impl ProbCutCheck {
+    ///Returns the shallow score at or above which the deep search is expected to reach beta
+    pub fn beta_bound(&self, beta: i32, threshold: f64) -> i32 {
+        ((beta as f64 - self.intercept + threshold * self.sigma) / self.slope).ceil() as i32
+    }
+
+    ///Returns the shallow score at or below which the deep search is expected to stay under alpha
+    pub fn alpha_bound(&self, alpha: i32, threshold: f64) -> i32 {
+        ((alpha as f64 - self.intercept - threshold * self.sigma) / self.slope).floor() as i32
+    }
+}
+
+/// Multi-ProbCut checks for every game stage and search depth
+pub struct ProbCutTable {
+    // Indexed by stage and depth
+    checks: Vec<Vec<Vec<ProbCutCheck>>>
+}
+
+impl ProbCutTable {
+    // Every check predicts the deep score with the shallow score unchanged
+    fn default_table() -> Self {
+        let checks = (0..STAGE_COUNT)
+            .map(|_| (0..=MAX_CALIBRATED_DEPTH)
+                .map(|depth| shallow_depths(depth).into_iter()
+                    .map(|shallow_depth| ProbCutCheck {
+                        shallow_depth,
+                        slope: 1.0,
+                        intercept: 0.0,
+                        sigma: DEFAULT_SIGMA + DEFAULT_SIGMA_PER_PLY * (depth - shallow_depth) as f64,
+                    })
+                    .collect())
+                .collect())
+            .collect();
+        Self { checks }
+    }
+
+    ///Returns the checks to try before a search of the given depth, cheapest first
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position being searched
+    ///  * 'depth' - The depth of the search the checks stand in for
+    pub fn checks(&self, board: &board::Board, depth: i32) -> Vec<ProbCutCheck> {
+        if depth < MIN_DEPTH {
+            return Vec::new();
+        }
+        // Deeper searches reuse the deepest checks, keeping the same depth difference
+        let calibrated = match depth > MAX_CALIBRATED_DEPTH {
+            true => MAX_CALIBRATED_DEPTH - (depth - MAX_CALIBRATED_DEPTH) % 2,
+            false => depth,
+        };
+        self.checks[stage(board)][calibrated as usize].iter()
+            .map(|check| ProbCutCheck { shallow_depth: check.shallow_depth + depth - calibrated, ..*check })
+            .collect()
+    }
+
+    // One line per check: stage, deep depth, shallow depth, slope, intercept and sigma
+    fn parse(text: &str) -> Option<Self> {
+        let mut table = Self::default_table();
+        for line in text.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 6 {
+                return None;
+            }
+            let stage: usize = fields[0].parse().ok()?;
+            let depth: usize = fields[1].parse().ok()?;
+            let check = ProbCutCheck {
+                shallow_depth: fields[2].parse().ok()?,
+                slope: fields[3].parse().ok()?,
+                intercept: fields[4].parse().ok()?,
+                sigma: fields[5].parse().ok()?,
+            };
+            let entry = table.checks.get_mut(stage)?.get_mut(depth)?
+                .iter_mut()
+                .find(|existing| existing.shallow_depth == check.shallow_depth)?;
+            // A slope of zero or less would turn the bounds around
+            if check.slope <= 0.0 {
+                return None;
+            }
+            *entry = check;
+        }
+        Some(table)
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::from("# stage depth shallow_depth slope intercept sigma\n");
+        for (stage, depths) in self.checks.iter().enumerate() {
+            for (depth, checks) in depths.iter().enumerate() {
+                for check in checks {
+                    text += &format!("{} {} {} {:.4} {:.2} {:.2}\n", stage, depth, check.shallow_depth, check.slope, check.intercept, check.sigma);
+                }
+            }
+        }
+        text
+    }
+}
+
+///Returns the highest selectivity level
+pub fn max_selectivity() -> usize {
+    SELECTIVITY_THRESHOLDS.len() - 1
+}
+
+///Returns the cut threshold of a selectivity level, or None when the level turns Multi-ProbCut off.
+/// Higher levels cut more often and search deeper, but miss more good moves.
+pub fn threshold(level: usize) -> Option<f64> {
+    match level {
+        0 => None,
+        _ => Some(SELECTIVITY_THRESHOLDS[level.min(max_selectivity())]),
+    }
+}
+
+///Returns the checks used by the search, read from the calibration file the first time.
+/// Falls back to uncalibrated defaults when the file does not exist.
+pub fn table() -> &'static ProbCutTable {
+    TABLE.get_or_init(|| match fs::read_to_string(constants::PROBCUT_FILE) {
+        Ok(text) => ProbCutTable::parse(&text).unwrap_or_else(|| {
+            println!("{} is not a valid calibration file, using the default ProbCut parameters", constants::PROBCUT_FILE);
+            ProbCutTable::default_table()
+        }),
+        Err(_) => ProbCutTable::default_table(),
+    })
+}
+
+///Plays self-play games and fits the Multi-ProbCut regressions from the scores of fixed depth
+/// searches at every position, then writes them to the calibration file.
+/// Games start with random moves so they do not all repeat the same opening.
+/// Positions the searches would solve to the end are left out, their scores are exact.
+///
+///  # Arguments
+///
+///  * 'games' - How many self-play games to record
+///  * 'max_depth' - The deepest search to calibrate, deeper searches reuse its checks
+///  * 'seed' - The seed of the random opening moves
+///
+///  # Examples
+///
+/// ```
+/// probcut::calibrate(20, 8, seed);
+/// ```
+pub fn calibrate(games: usize, max_depth: i32, seed: u64) {
+    let max_depth = max_depth.clamp(MIN_DEPTH, MAX_CALIBRATED_DEPTH);
+    let start_time = Instant::now();
+    // Scores of every search depth, per position and stage
+    let mut samples: Vec<Vec<Vec<i32>>> = vec![Vec::new(); STAGE_COUNT];
+    let mut rng = seed::rng(seed);
+    println!("Calibrating with seed {}", seed);
+    for game in 0..games {
+        let mut board = board::Board::new();
+        let mut ply = 0;
+        while !board::Board::check_game_over(&board) {
+            let possible_moves = board.get_possible_moves();
+            if possible_moves == 0 {
+                board.pass_turn();
+                continue;
+            }
+            // Searches that reach the end of the game are exact and say nothing about the error
+            let best_move = match board.count_empty() as i32 > max_depth {
+                true => {
+                    let (scores, best_move) = minimax::fixed_depth_scores(&board, max_depth);
+                    samples[stage(&board)].push(scores);
+                    best_move
+                }
+                false => minimax::fixed_depth_scores(&board, 1).1,
+            };
+            let selected_move = match ply < OPENING_RANDOM_MOVES {
+                true => nth_move(possible_moves, rng.gen_range(0..possible_moves.count_ones())),
+                false => best_move,
+            };
+            board.place_move(&possible_moves, selected_move);
+            ply += 1;
+        }
+        println!("Game {} of {} recorded, {:.0}s elapsed", game + 1, games, start_time.elapsed().as_secs_f64());
+    }
+
+    let mut table = ProbCutTable::default_table();
+    for (stage, stage_samples) in samples.iter().enumerate() {
+        for depth in MIN_DEPTH..=max_depth {
+            for check in table.checks[stage][depth as usize].iter_mut() {
+                let points: Vec<(f64, f64)> = stage_samples.iter()
+                    .map(|scores| (scores[check.shallow_depth as usize - 1], scores[depth as usize - 1]))
+                    .filter(|&(shallow, deep)| !minimax::is_win_score(shallow) && !minimax::is_win_score(deep))
+                    .map(|(shallow, deep)| (shallow as f64, deep as f64))
+                    .collect();
+                if points.len() < MIN_SAMPLES {
+                    continue;
+                }
+                if let Some((slope, intercept, sigma)) = fit(&points) {
+                    *check = ProbCutCheck { shallow_depth: check.shallow_depth, slope, intercept, sigma };
+                    println!("stage {} depth {} from depth {}: slope {:.3} intercept {:.1} sigma {:.1} ({} positions)",
+                             stage, depth, check.shallow_depth, slope, intercept, sigma, points.len());
+                }
+            }
+        }
+    }
+    match fs::write(constants::PROBCUT_FILE, table.to_text()) {
+        Ok(_) => println!("Calibration written to {}", constants::PROBCUT_FILE),
+        Err(error) => println!("Could not write {}: {}", constants::PROBCUT_FILE, error),
+    }
+}
+
+// Least squares line through the points, with the standard deviation of the residuals
+fn fit(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|point| point.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|point| point.1).sum::<f64>() / count;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 || covariance <= 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    let intercept = mean_y - slope * mean_x;
+    let residuals: f64 = points.iter().map(|(x, y)| (y - slope * x - intercept).powi(2)).sum();
+    Some((slope, intercept, (residuals / count).sqrt()))
+}
+
+// Shallow searches for a deep search: at most half as deep with the same parity, deepest first
+fn shallow_depths(depth: i32) -> Vec<i32> {
+    if depth < MIN_DEPTH {
+        return Vec::new();
+    }
+    let deepest = depth / 2 - (depth / 2 - depth).rem_euclid(2);
+    let mut depths: Vec<i32> = (1..=deepest).rev().step_by(2).take(CHECKS_PER_DEPTH).collect();
+    // The cheaper search is tried first
+    depths.reverse();
+    depths
+}
+
+fn stage(board: &board::Board) -> usize {
+    let discs = board.count_squares() - board.count_empty();
+    ((discs / DISCS_PER_STAGE) as usize).min(STAGE_COUNT - 1)
+}
+
+// The index-th set bit of the moves
+fn nth_move(mut moves: u64, index: u32) -> u64 {
+    for _ in 0..index {
+        moves &= moves - 1;
+    }
+    moves & moves.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_recovers_a_line() {
+        let points: Vec<(f64, f64)> = (0..10).map(|x| (x as f64, 2.0 * x as f64 + 3.0)).collect();
+        let (slope, intercept, sigma) = fit(&points).unwrap();
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!((intercept - 3.0).abs() < 1e-9);
+        assert!(sigma < 1e-9);
+    }
+
+    #[test]
+    fn shallow_depths_keep_parity() {
+        assert_eq!(shallow_depths(3), vec![1]);
+        assert_eq!(shallow_depths(4), vec![2]);
+        assert_eq!(shallow_depths(7), vec![1, 3]);
+        assert_eq!(shallow_depths(12), vec![4, 6]);
+    }
+
+    #[test]
+    fn calibration_file_round_trips() {
+        let mut table = ProbCutTable::default_table();
+        table.checks[2][8][1] = ProbCutCheck { shallow_depth: 4, slope: 0.9, intercept: -3.5, sigma: 61.25 };
+        let parsed = ProbCutTable::parse(&table.to_text()).unwrap();
+        assert_eq!(parsed.checks, table.checks);
+    }
+}
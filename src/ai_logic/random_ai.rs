@@ -1,17 +1,20 @@
 use rand::Rng;
 
-pub fn get_random_move(possible_moves: u64) -> u64 {
-    // Count positions in possible_moves and place the indexes in an array
-    let mut move_positions: Vec<usize> = Vec::new();
-    let mut bit_mask = 1u64;
-    for index in 0..64 {
-        // get position at I to see if it is a move
-        if(possible_moves & bit_mask) != 0 {
-            move_positions.push(index);
-        }
-        bit_mask <<= 1;
+use crate::bitboard::Bitboard;
 
+pub fn get_random_move<B: Bitboard, R: Rng>(possible_moves: B, rng: &mut R) -> B {
+    // No legal moves, the player has to pass
+    if possible_moves == B::EMPTY {
+        return B::EMPTY;
     }
-    let random_index = rand::thread_rng().gen_range(0..move_positions.len());
-    1 << move_positions[random_index]
-}
\ No newline at end of file
+    // Split possible_moves into its single squares
+    let mut move_positions: Vec<B> = Vec::new();
+    let mut remaining = possible_moves;
+    while remaining != B::EMPTY {
+        let square = remaining.lowest_square();
+        move_positions.push(square);
+        remaining ^= square;
+    }
+    let random_index = rng.gen_range(0..move_positions.len());
+    move_positions[random_index]
+}
@@ -0,0 +1,135 @@
+//Imports
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+//Modules
+use crate::board;
+use crate::ai_logic::{minimax, EngineOptions};
+use crate::ai_logic::transposition::TranspositionTable;
+
+// Depth and nodes of the search that measures how much a move lost
+const LOSS_DEPTH: i32 = 4;
+const LOSS_NODES: u64 = 200_000;
+const LOSS_TIME: Duration = Duration::from_secs(1);
+
+/// How one strength level limits the minimax engine
+#[derive(Clone, Copy, Debug)]
+pub struct StrengthLevel {
+    // Deepest iteration of the search
+    pub depth: i32,
+    // Nodes the search may visit per move, on top of the time the clock allows
+    pub nodes: u64,
+    // Every root score is moved by a random amount of up to this many points before the best is picked
+    pub noise: i32,
+    // Chance of playing a random move other than the best one
+    pub blunder: f64
+}
+
+/// Strength levels from the weakest to the strongest, level 1 is the first entry
+pub const LEVELS: [StrengthLevel; 15] = [
+    StrengthLevel { depth: 1, nodes: 1_000, noise: 300, blunder: 0.40 },
+    StrengthLevel { depth: 1, nodes: 1_000, noise: 200, blunder: 0.30 },
+    StrengthLevel { depth: 2, nodes: 2_000, noise: 150, blunder: 0.22 },
+    StrengthLevel { depth: 2, nodes: 5_000, noise: 100, blunder: 0.15 },
+    StrengthLevel { depth: 3, nodes: 10_000, noise: 80, blunder: 0.10 },
+    StrengthLevel { depth: 3, nodes: 20_000, noise: 60, blunder: 0.07 },
+    StrengthLevel { depth: 4, nodes: 50_000, noise: 40, blunder: 0.05 },
+    StrengthLevel { depth: 4, nodes: 100_000, noise: 30, blunder: 0.03 },
+    StrengthLevel { depth: 5, nodes: 200_000, noise: 20, blunder: 0.02 },
+    StrengthLevel { depth: 6, nodes: 500_000, noise: 12, blunder: 0.01 },
+    StrengthLevel { depth: 7, nodes: 1_000_000, noise: 8, blunder: 0.005 },
+    StrengthLevel { depth: 8, nodes: 2_000_000, noise: 4, blunder: 0.0 },
+    StrengthLevel { depth: 10, nodes: 5_000_000, noise: 0, blunder: 0.0 },
+    StrengthLevel { depth: 12, nodes: 20_000_000, noise: 0, blunder: 0.0 },
+    StrengthLevel { depth: 60, nodes: u64::MAX, noise: 0, blunder: 0.0 },
+];
+
+///Returns the settings of a level from 1 to LEVELS.len(), or None for any other number
+pub fn level(number: usize) -> Option<StrengthLevel> {
+    number.checked_sub(1).and_then(|index| LEVELS.get(index)).copied()
+}
+
+///Picks a move at the given strength level. The root moves are scored by a search limited to the
+/// level's depth and nodes, the scores are blurred with the level's noise and the best is played,
+/// unless the level blunders and plays another move at random.
+///
+///  # Arguments
+///
+///  * 'board' - The current game-state, the player to move must have a legal move
+///  * 'level' - The strength level to play at
+///  * 'duration' - How long the search may run at most
+///  * 'options' - The engine settings
+///  * 'table' - The transposition table to search with
+///  * 'rng' - The random number generator for the noise and blunders
+///
+///  # Examples
+///
+/// ```
+/// let selected_move = strength::get_level_move(&board, strength::level(5).unwrap(), Duration::from_secs(5), &options, &mut table, &mut rng);
+/// ```
+pub fn get_level_move<R: Rng>(board: &board::Board, level: StrengthLevel, duration: Duration, options: &EngineOptions, table: &mut TranspositionTable, rng: &mut R) -> u64 {
+    let scores = minimax::limited_scores(board, level.depth, level.nodes, Instant::now() + duration, options, table);
+    if scores.len() > 1 && rng.gen_bool(level.blunder) {
+        return scores[rng.gen_range(1..scores.len())].0;
+    }
+    // max_by_key keeps the last of equal scores, so ties go to the move the search ranked first
+    scores.into_iter()
+        .rev()
+        .map(|(chosen_move, score)| (chosen_move, score.saturating_add(rng.gen_range(-level.noise..=level.noise))))
+        .max_by_key(|&(_, score)| score)
+        .map_or(0, |(chosen_move, _)| chosen_move)
+}
+
+///Returns how many evaluation points the move lost against the best move of a quick search,
+/// 0 for the best move and for forced moves
+///
+///  # Arguments
+///
+///  * 'board' - The position the move was played in
+///  * 'played_move' - The move that was played
+///  * 'options' - The engine settings
+///  * 'table' - The transposition table to search with
+pub fn move_loss(board: &board::Board, played_move: u64, options: &EngineOptions, table: &mut TranspositionTable) -> i32 {
+    if board.get_possible_moves().count_ones() <= 1 {
+        return 0;
+    }
+    let scores = minimax::limited_scores(board, LOSS_DEPTH, LOSS_NODES, Instant::now() + LOSS_TIME, options, table);
+    let best = scores.first().map_or(0, |&(_, score)| score);
+    scores.iter()
+        .find(|&&(chosen_move, _)| chosen_move == played_move)
+        .map_or(0, |&(_, score)| (best - score).max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_numbered_from_one() {
+        assert!(level(0).is_none());
+        assert!(level(LEVELS.len() + 1).is_none());
+        assert_eq!(level(1).map(|level| level.depth), Some(LEVELS[0].depth));
+        // Stronger levels never search less or make more mistakes
+        for pair in LEVELS.windows(2) {
+            assert!(pair[1].depth >= pair[0].depth && pair[1].nodes >= pair[0].nodes);
+            assert!(pair[1].noise <= pair[0].noise && pair[1].blunder <= pair[0].blunder);
+        }
+    }
+
+    #[test]
+    fn exact_levels_play_the_best_move() {
+        let board = board::Board::new();
+        let options = EngineOptions::default();
+        let level = StrengthLevel { depth: 3, nodes: u64::MAX, noise: 0, blunder: 0.0 };
+        let end_time = Instant::now() + Duration::from_secs(60);
+        let mut rng = crate::seed::rng(0);
+        let mut table = TranspositionTable::with_size(1 << 16);
+        let best_move = minimax::limited_scores(&board, 3, u64::MAX, end_time, &options, &mut table)[0].0;
+        assert_eq!(get_level_move(&board, level, Duration::from_secs(60), &options, &mut table, &mut rng), best_move);
+        // A level that always blunders never plays it
+        let blunderer = StrengthLevel { blunder: 1.0, ..level };
+        for _ in 0..10 {
+            assert_ne!(get_level_move(&board, blunderer, Duration::from_secs(60), &options, &mut table, &mut rng), best_move);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//Imports
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+//Modules
+use crate::{board, constants, seed};
+use crate::ai_logic::monte_carlo_tree_search::{MctsSettings, Tree};
+use crate::ai_logic::neural_network::{self, Network, Optimizer, Sample};
+
+// Shape of a new network. Small enough that an iteration of self-play runs in minutes
+const FEATURES: u32 = neural_network::FEATURE_MOBILITY | neural_network::FEATURE_FRONTIER | neural_network::FEATURE_EMPTIES;
+const HIDDEN_LAYERS: [usize; 2] = [64, 64];
+// Self-play settings
+const SELF_PLAY_PLAYOUTS: usize = 200;
+// Moves are drawn in proportion to their visits for this many plies, then the most visited is played
+const TEMPERATURE_PLIES: usize = 16;
+const DIRICHLET_ALPHA: f32 = 0.5;
+const NOISE_FRACTION: f32 = 0.25;
+// Training settings
+const REPLAY_WINDOW: usize = 200_000;
+const BATCH_SIZE: usize = 256;
+const TRAINING_EPOCHS: usize = 2;
+const LEARNING_RATE: f32 = 0.01;
+const MOMENTUM: f32 = 0.9;
+const WEIGHT_DECAY: f32 = 1e-4;
+// Gating settings. The candidate replaces the best network if it scores at least the threshold
+const GATING_GAMES: usize = 40;
+const GATING_PLAYOUTS: usize = 200;
+const GATING_RANDOM_PLIES: usize = 4;
+const GATING_THRESHOLD: f32 = 0.55;
+// Bytes of one sample in the samples file
+const SAMPLE_BYTES: usize = 8 + 8 + 64 * 4 + 4;
+
+///Runs the self-play training loop. Every iteration plays games between the best network and
+/// itself, adds their positions in all 8 board symmetries to the samples file, trains a candidate
+/// on the most recent samples and plays it against the best network. A candidate that wins the
+/// match becomes the best network and is written to the weights file the neural evaluator reads.
+///
+///  # Arguments
+///
+///  * 'iterations' - How many rounds of self-play, training and gating to run
+///  * 'games' - How many self-play games to play per iteration
+///  * 'seed' - The seed of the run, every iteration and game gets its own stream of it
+///
+///  # Examples
+///
+/// ```
+/// training::run_training(10, 100, seed);
+/// ```
+pub fn run_training(iterations: usize, games: usize, seed: u64) {
+    println!("Training with seed {}", seed);
+    let mut rng = seed::rng(seed);
+    if let Err(error) = fs::create_dir_all(constants::TRAINING_DIRECTORY) {
+        println!("Could not create {}: {}", constants::TRAINING_DIRECTORY, error);
+        return;
+    }
+    // Carry on from the current best network if it can guide the tree search
+    let mut best = match fs::read(constants::NEURAL_NETWORK_FILE).map_err(|error| error.to_string()).and_then(|bytes| Network::from_bytes(&bytes)) {
+        Ok(network) if network.has_policy() => {
+            println!("Continuing from {}", constants::NEURAL_NETWORK_FILE);
+            network
+        }
+        _ => {
+            println!("Starting from a new network");
+            Network::random(FEATURES, &HIDDEN_LAYERS, true, &mut rng)
+        }
+    };
+    let samples_path = format!("{}/samples.bin", constants::TRAINING_DIRECTORY);
+
+    for iteration in 1..=iterations {
+        let start_time = Instant::now();
+        let iteration_seed = seed::split(seed, iteration as u64);
+        let samples = self_play(&best, games, SELF_PLAY_PLAYOUTS, seed::split(iteration_seed, 0));
+        if let Err(error) = append_samples(&samples_path, &samples) {
+            println!("Could not write {}: {}", samples_path, error);
+            return;
+        }
+        println!("Iteration {}: {} samples from {} games in {:.0}s", iteration, samples.len(), games, start_time.elapsed().as_secs_f64());
+
+        let mut window = read_samples(&samples_path, REPLAY_WINDOW).unwrap_or(samples);
+        let mut candidate = best.clone();
+        let mut optimizer = Optimizer::new(&candidate, LEARNING_RATE, MOMENTUM, WEIGHT_DECAY);
+        for epoch in 1..=TRAINING_EPOCHS {
+            window.shuffle(&mut rng);
+            let (mut value_loss, mut policy_loss, mut batches) = (0.0, 0.0, 0);
+            for batch in window.chunks(BATCH_SIZE) {
+                let (value, policy) = candidate.train_batch(batch, &mut optimizer);
+                value_loss += value;
+                policy_loss += policy;
+                batches += 1;
+            }
+            println!("Epoch {}: value loss {:.4}, policy loss {:.4} over {} samples", epoch, value_loss / batches.max(1) as f32, policy_loss / batches.max(1) as f32, window.len());
+        }
+
+        let score = play_match(&candidate, &best, GATING_GAMES, seed::split(iteration_seed, 1));
+        println!("Candidate scored {:.1}% against the best network", score * 100.0);
+        if score >= GATING_THRESHOLD {
+            best = candidate;
+            // Earlier runs may have left generations behind, so count them instead of the iterations
+            let previous = fs::read_dir(constants::TRAINING_DIRECTORY).map_or(0, |entries| entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("generation-"))
+                .count());
+            let generation = format!("{}/generation-{}.bin", constants::TRAINING_DIRECTORY, previous + 1);
+            for path in [constants::NEURAL_NETWORK_FILE, generation.as_str()] {
+                match best.save(path) {
+                    Ok(_) => println!("New best network written to {}", path),
+                    Err(error) => println!("{}", error),
+                }
+            }
+        }
+    }
+}
+
+// Plays the games on every core and returns their samples in all symmetries. Every game draws
+// from its own stream of the seed, and the samples come back in game order whichever thread
+// finished first, so the same seed always gives the same training data
+fn self_play(network: &Network, games: usize, playouts: usize, seed: u64) -> Vec<Sample> {
+    let next_game = AtomicUsize::new(0);
+    // The samples of every game, in the slot of its number
+    let slots: Mutex<Vec<Vec<Sample>>> = Mutex::new(vec![Vec::new(); games]);
+    thread::scope(|scope| {
+        for _ in 0..workers() {
+            scope.spawn(|| {
+                loop {
+                    let game = next_game.fetch_add(1, Ordering::Relaxed);
+                    if game >= games {
+                        break;
+                    }
+                    let samples: Vec<Sample> = self_play_game(network, playouts, &mut seed::rng(seed::split(seed, game as u64)))
+                        .iter()
+                        .flat_map(symmetries)
+                        .collect();
+                    if let Ok(mut slots) = slots.lock() {
+                        slots[game] = samples;
+                    }
+                }
+            });
+        }
+    });
+    slots.into_inner().unwrap_or_default().concat()
+}
+
+fn self_play_game<R: Rng>(network: &Network, playouts: usize, rng: &mut R) -> Vec<Sample> {
+    let mut board = board::Board::new();
+    // Every position with the search's visit distribution and the player to move
+    let mut positions: Vec<(u64, u64, [f32; 64], constants::Color)> = Vec::new();
+    while !board::Board::check_game_over(&board) {
+        let possible_moves = board.get_possible_moves();
+        if possible_moves == 0 {
+            board.pass_turn();
+            continue;
+        }
+        let mut tree = Tree::new(board.clone(), MctsSettings::network(), Some(network), rng.gen());
+        tree.add_root_noise(DIRICHLET_ALPHA, NOISE_FRACTION, rng);
+        tree.run(playouts);
+        let player = board.current_player;
+        positions.push((board.get_color_bitboard(player), board.get_color_bitboard(player.opponent()), tree.visit_distribution(), player));
+        let selected_move = match positions.len() <= TEMPERATURE_PLIES {
+            true => tree.sample_move(rng),
+            false => tree.best_move(),
+        };
+        board.place_move(&possible_moves, selected_move);
+    }
+
+    let black_margin = board.count_discs(constants::Color::Black) as i32 - board.count_discs(constants::Color::White) as i32;
+    positions.into_iter()
+        .map(|(player, opponent, policy, color)| {
+            let result = match color {
+                constants::Color::Black => black_margin.signum(),
+                constants::Color::White => -black_margin.signum(),
+            };
+            Sample { player, opponent, policy, value: result as f32 }
+        })
+        .collect()
+}
+
+// Plays the networks against each other with colors alternating and returns the share of the
+// points first scored, counting a draw as half a point
+fn play_match(first: &Network, second: &Network, games: usize, seed: u64) -> f32 {
+    let next_game = AtomicUsize::new(0);
+    let points = Mutex::new(0.0f32);
+    thread::scope(|scope| {
+        for _ in 0..workers() {
+            scope.spawn(|| {
+                loop {
+                    let game = next_game.fetch_add(1, Ordering::Relaxed);
+                    if game >= games {
+                        break;
+                    }
+                    let mut rng = seed::rng(seed::split(seed, game as u64));
+                    let first_color = match game % 2 {
+                        0 => constants::Color::Black,
+                        _ => constants::Color::White,
+                    };
+                    let mut board = board::Board::new();
+                    let mut ply = 0;
+                    while !board::Board::check_game_over(&board) {
+                        let possible_moves = board.get_possible_moves();
+                        if possible_moves == 0 {
+                            board.pass_turn();
+                            continue;
+                        }
+                        let network = match board.current_player == first_color {
+                            true => first,
+                            false => second,
+                        };
+                        let mut tree = Tree::new(board.clone(), MctsSettings::network(), Some(network), rng.gen());
+                        tree.run(GATING_PLAYOUTS);
+                        // A few drawn moves at the start keep the games from all being the same
+                        let selected_move = match ply < GATING_RANDOM_PLIES {
+                            true => tree.sample_move(&mut rng),
+                            false => tree.best_move(),
+                        };
+                        board.place_move(&possible_moves, selected_move);
+                        ply += 1;
+                    }
+                    let margin = board.count_discs(first_color) as i32 - board.count_discs(first_color.opponent()) as i32;
+                    if let Ok(mut points) = points.lock() {
+                        *points += (margin.signum() as f32 + 1.0) / 2.0;
+                    }
+                }
+            });
+        }
+    });
+    points.into_inner().unwrap_or(0.0) / games.max(1) as f32
+}
+
+fn workers() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get())
+}
+
+// The sample in all 8 rotations and reflections of the board, which all have the same result
+fn symmetries(sample: &Sample) -> Vec<Sample> {
+    (0..8)
+        .map(|symmetry| {
+            let mut policy = [0.0; 64];
+            for (square, &share) in sample.policy.iter().enumerate() {
+                policy[transform(1 << square, symmetry).trailing_zeros() as usize] = share;
+            }
+            Sample {
+                player: transform(sample.player, symmetry),
+                opponent: transform(sample.opponent, symmetry),
+                policy,
+                value: sample.value,
+            }
+        })
+        .collect()
+}
+
+// One of the 8 symmetries of the square: bit 0 mirrors the files, bit 1 the ranks and bit 2
+// reflects along the a1-h8 diagonal
+fn transform(mut bits: u64, symmetry: usize) -> u64 {
+    if symmetry & 4 != 0 {
+        bits = flip_diagonal(bits);
+    }
+    if symmetry & 2 != 0 {
+        bits = bits.swap_bytes();
+    }
+    if symmetry & 1 != 0 {
+        bits = mirror_files(bits);
+    }
+    bits
+}
+
+fn mirror_files(mut bits: u64) -> u64 {
+    bits = ((bits >> 1) & 0x5555555555555555) | ((bits & 0x5555555555555555) << 1);
+    bits = ((bits >> 2) & 0x3333333333333333) | ((bits & 0x3333333333333333) << 2);
+    ((bits >> 4) & 0x0f0f0f0f0f0f0f0f) | ((bits & 0x0f0f0f0f0f0f0f0f) << 4)
+}
+
+fn flip_diagonal(mut bits: u64) -> u64 {
+    let mut swap = 0x0f0f0f0f00000000 & (bits ^ (bits << 28));
+    bits ^= swap ^ (swap >> 28);
+    swap = 0x3333000033330000 & (bits ^ (bits << 14));
+    bits ^= swap ^ (swap >> 14);
+    swap = 0x5500550055005500 & (bits ^ (bits << 7));
+    bits ^ swap ^ (swap >> 7)
+}
+
+// Samples are stored back to back, little endian: the player and opponent bitboards as u64,
+// then the 64 policy shares and the result as f32
+fn append_samples(path: &str, samples: &[Sample]) -> std::io::Result<()> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(samples.len() * SAMPLE_BYTES);
+    for sample in samples {
+        bytes.extend_from_slice(&sample.player.to_le_bytes());
+        bytes.extend_from_slice(&sample.opponent.to_le_bytes());
+        for value in sample.policy.iter().chain([sample.value].iter()) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(&bytes)
+}
+
+// The most recent samples of the file, at most limit of them
+fn read_samples(path: &str, limit: usize) -> std::io::Result<Vec<Sample>> {
+    if !Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+    let bytes = fs::read(path)?;
+    let count = bytes.len() / SAMPLE_BYTES;
+    let skip = count.saturating_sub(limit);
+    let read_f32 = |chunk: &[u8]| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    Ok(bytes.chunks_exact(SAMPLE_BYTES)
+        .skip(skip)
+        .map(|record| {
+            let mut policy = [0.0; 64];
+            for (square, share) in policy.iter_mut().enumerate() {
+                *share = read_f32(&record[16 + square * 4..]);
+            }
+            Sample {
+                player: u64::from_le_bytes(record[0..8].try_into().unwrap_or_default()),
+                opponent: u64::from_le_bytes(record[8..16].try_into().unwrap_or_default()),
+                policy,
+                value: read_f32(&record[SAMPLE_BYTES - 4..]),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetries_keep_moves_legal() {
+        let board = board::Board::new();
+        let moves = board.get_possible_moves();
+        let mut policy = [0.0; 64];
+        let mut remaining = moves;
+        while remaining != 0 {
+            policy[remaining.trailing_zeros() as usize] = 0.25;
+            remaining &= remaining - 1;
+        }
+        let sample = Sample { player: board.black, opponent: board.white, policy, value: 1.0 };
+        let transformed = symmetries(&sample);
+        for symmetry in &transformed {
+            let legal = board::Board::generate_moves(symmetry.player, symmetry.opponent);
+            let chosen = symmetry.policy.iter().enumerate()
+                .filter(|(_, &share)| share > 0.0)
+                .fold(0u64, |bits, (square, _)| bits | (1 << square));
+            assert_eq!(chosen, legal);
+        }
+        // An uneven shape looks different in every symmetry
+        let shapes: Vec<u64> = (0..8).map(|symmetry| transform(0x0000000000000f03, symmetry)).collect();
+        for (index, shape) in shapes.iter().enumerate() {
+            assert_eq!(shape.count_ones(), 6);
+            assert!(!shapes[..index].contains(shape));
+        }
+        assert_eq!(transform(1, 1), 1 << 7);
+        assert_eq!(transform(1, 2), 1 << 56);
+        assert_eq!(transform(2, 4), 1 << 8);
+    }
+
+    #[test]
+    fn self_play_gives_the_same_samples_for_the_same_seed() {
+        let network = Network::random(FEATURES, &HIDDEN_LAYERS, true, &mut seed::rng(7));
+        let samples = self_play(&network, 4, 8, 11);
+        assert!(!samples.is_empty());
+        assert_eq!(self_play(&network, 4, 8, 11), samples);
+    }
+}
@@ -0,0 +1,209 @@
+//Modules
+use crate::{board, constants};
+
+// Number of entries of the table a game keeps, a power of two so the index is the low bits of the hash
+const TABLE_SIZE: usize = 1 << 20;
+// Keeps black and white discs on the same square from cancelling out in the hash
+const WHITE_SEED: u64 = 0x9e3779b97f4a7c15;
+const SIDE_TO_MOVE_KEY: u64 = 0xd1b54a32d192ed03;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    key: u64,
+    best_move: u64,
+    // Remaining depth the bounds were searched to
+    depth: i32,
+    lower: i32,
+    upper: i32,
+    // The search that stored the entry, entries of earlier searches are treated as empty
+    generation: u32
+}
+
+impl Default for Entry {
+    fn default() -> Self {
+        Self { key: 0, best_move: 0, depth: -1, lower: i32::MIN, upper: i32::MAX, generation: 0 }
+    }
+}
+
+/// Remembers the best move found for positions already searched, so it can be tried first next time,
+/// and the bounds on their score so a search that already answered a question is not repeated.
+/// The table is allocated once by its owner and reused, but every search starts a new generation,
+/// so nothing one search stores is seen by the next
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    generation: u32
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        Self::with_size(TABLE_SIZE)
+    }
+
+    ///Creates a table with room for the given number of entries, rounded up to a power of two.
+    /// Callers that only run short searches use a smaller table
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'size' - The number of entries
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let mut table = TranspositionTable::with_size(1 << 16);
+    /// ```
+    pub fn with_size(size: usize) -> Self {
+        Self { entries: vec![Entry::default(); size.max(1).next_power_of_two()], generation: 1 }
+    }
+
+    ///Forgets every entry without touching them, so a new search starts from an empty table.
+    /// Called at the start of every search
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        // After wrapping around the oldest entries would look current again
+        if self.generation == 0 {
+            self.entries.fill(Entry::default());
+            self.generation = 1;
+        }
+    }
+
+    // The entry stored for the position by the current search
+    fn entry(&self, key: u64) -> Option<&Entry> {
+        let entry = &self.entries[key as usize & (self.entries.len() - 1)];
+        match entry.key == key && entry.generation == self.generation {
+            true => Some(entry),
+            false => None,
+        }
+    }
+
+    ///Returns the best move stored for the position, or 0 if there is none
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'key' - The hash of the position
+    pub fn probe_move(&self, key: u64) -> u64 {
+        self.entry(key).map_or(0, |entry| entry.best_move)
+    }
+
+    ///Returns the lower and upper bound stored for the position if it was searched at least as deep
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'key' - The hash of the position
+    ///  * 'depth' - The remaining depth of the search asking
+    pub fn probe_bounds(&self, key: u64, depth: i32) -> Option<(i32, i32)> {
+        self.entry(key)
+            .filter(|entry| entry.depth >= depth)
+            .map(|entry| (entry.lower, entry.upper))
+    }
+
+    ///Stores the best move for the position and what the score tells about its value,
+    /// replacing whatever was in its slot. A score at or below alpha is an upper bound,
+    /// at or above beta a lower bound, and exact in between. Bounds from a search of the
+    /// same position to the same depth are combined.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'key' - The hash of the position
+    ///  * 'best_move' - The best move found for the position as a bitboard
+    ///  * 'depth' - The remaining depth the position was searched to
+    ///  * 'score' - The score the search returned
+    ///  * 'alpha' - The lower end of the window the position was searched with
+    ///  * 'beta' - The upper end of the window
+    pub fn store(&mut self, key: u64, best_move: u64, depth: i32, score: i32, alpha: i32, beta: i32) {
+        let generation = self.generation;
+        let index = key as usize & (self.entries.len() - 1);
+        let slot = &mut self.entries[index];
+        let (mut lower, mut upper) = (i32::MIN, i32::MAX);
+        if score > alpha {
+            lower = score;
+        }
+        if score < beta {
+            upper = score;
+        }
+        if slot.key == key && slot.depth == depth && slot.generation == generation {
+            lower = lower.max(slot.lower);
+            upper = upper.min(slot.upper);
+        }
+        *slot = Entry { key, best_move, depth, lower, upper, generation };
+    }
+}
+
+///Returns a 64 bit hash of the position including the player to move
+///
+///  # Arguments
+///
+///  * 'board' - The position to hash
+pub fn hash(board: &board::Board) -> u64 {
+    let side = match board.current_player {
+        constants::Color::Black => 0,
+        constants::Color::White => SIDE_TO_MOVE_KEY,
+    };
+    mix(board.black) ^ mix(board.white ^ WHITE_SEED).rotate_left(17) ^ side
+}
+
+// splitmix64 finalizer, spreads every input bit over the whole output
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVE: u64 = 1 << 19;
+
+    #[test]
+    fn a_hit_returns_the_stored_bound_and_move() {
+        let mut table = TranspositionTable::with_size(1 << 8);
+        let key = hash(&board::Board::new());
+        assert_eq!(table.probe_move(key), 0);
+        assert_eq!(table.probe_bounds(key, 0), None);
+
+        // An upper bound when the score is at alpha and a lower bound when it is at beta
+        table.store(key, MOVE, 4, -50, -50, 50);
+        assert_eq!(table.probe_move(key), MOVE);
+        assert_eq!(table.probe_bounds(key, 4), Some((i32::MIN, -50)));
+        // Bounds of the same depth are combined
+        table.store(key, MOVE, 4, -80, -81, -80);
+        assert_eq!(table.probe_bounds(key, 4), Some((-80, -50)));
+        // Exact inside the window, answering searches no deeper than it
+        table.store(key, MOVE << 1, 5, 10, -50, 50);
+        assert_eq!(table.probe_move(key), MOVE << 1);
+        assert_eq!(table.probe_bounds(key, 5), Some((10, 10)));
+        assert_eq!(table.probe_bounds(key, 3), Some((10, 10)));
+        assert_eq!(table.probe_bounds(key, 6), None);
+        // A different position in the same slot is a miss
+        let other = key ^ (1 << 40);
+        assert_eq!(table.probe_move(other), 0);
+        assert_eq!(table.probe_bounds(other, 0), None);
+    }
+
+    #[test]
+    fn a_new_search_starts_from_an_empty_table() {
+        let mut table = TranspositionTable::with_size(100);
+        assert_eq!(table.entries.len(), 128);
+        let key = hash(&board::Board::new());
+        table.store(key, MOVE, 4, 10, -50, 50);
+        table.new_search();
+        assert_eq!(table.probe_move(key), 0);
+        assert_eq!(table.probe_bounds(key, 0), None);
+        // Stale bounds are not combined with new ones
+        table.store(key, MOVE, 4, -50, -50, 50);
+        assert_eq!(table.probe_bounds(key, 4), Some((i32::MIN, -50)));
+
+        // Wrapping the generation around clears the entries for real
+        table.generation = u32::MAX;
+        table.store(key, MOVE, 4, 10, -50, 50);
+        table.new_search();
+        assert_eq!(table.generation, 1);
+        assert_eq!(table.probe_move(key), 0);
+    }
+}
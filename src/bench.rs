@@ -0,0 +1,114 @@
+//Imports
+use std::time::Instant;
+
+//Modules
+use crate::board::Board;
+use crate::flip_tables;
+
+// Expected perft counts from the starting position, passes count as a ply
+const PERFT_RESULTS: [u64; 11] = [1, 4, 12, 56, 244, 1396, 8200, 55092, 390216, 3005288, 24571284];
+
+/// A move generator and flip function pair to benchmark
+struct Generator {
+    name: &'static str,
+    moves: fn(u64, u64) -> u64,
+    flips: fn(u64, u64, u64) -> u64,
+}
+
+///Runs perft from the starting position with every move generator and prints
+/// the nodes per second of each one, so the implementations can be compared.
+///
+///  # Arguments
+///
+///  * 'depth' - How many plies deep to run perft
+///
+///  # Examples
+///
+/// ```
+/// bench::run_bench(9);
+/// ```
+pub fn run_bench(depth: usize) {
+    let generators = [
+        Generator { name: "shift loop (legacy)", moves: legacy::generate_moves, flips: legacy::compute_flips },
+        Generator { name: "kogge-stone", moves: Board::generate_moves, flips: Board::compute_flips },
+        Generator { name: "flip tables", moves: Board::generate_moves, flips: flip_tables::compute_flips },
+    ];
+    // Table construction is not part of the measurement
+    flip_tables::initialize();
+    let start = Board::new();
+    println!("perft({}) from the starting position", depth);
+    for generator in generators {
+        let start_time = Instant::now();
+        let nodes = perft(&generator, start.black, start.white, depth, false);
+        let elapsed = start_time.elapsed().as_secs_f64();
+        let status = match PERFT_RESULTS.get(depth) {
+            Some(&expected) if expected == nodes => "ok",
+            Some(_) => "WRONG",
+            None => "unverified",
+        };
+        println!("{:<22}{:>12} nodes\t{:>8.3}s\t{:>12.0} nodes/s\t{}", generator.name, nodes, elapsed, nodes as f64 / elapsed.max(1e-9), status);
+    }
+}
+
+// Counts the leaf nodes depth plies below the position
+fn perft(generator: &Generator, player: u64, opponent: u64, depth: usize, passed: bool) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = (generator.moves)(player, opponent);
+    if moves == 0 {
+        // Game over when both sides pass
+        if passed {
+            return 1;
+        }
+        return perft(generator, opponent, player, depth - 1, true);
+    }
+
+    let mut nodes: u64 = 0;
+    let mut remaining = moves;
+    while remaining != 0 {
+        let current_move = remaining & remaining.wrapping_neg();
+        remaining ^= current_move;
+        let flips = (generator.flips)(player, opponent, current_move);
+        nodes += perft(generator, opponent ^ flips, player | current_move | flips, depth - 1, false);
+    }
+    nodes
+}
+
+/// The original shift loop move generation, kept as the baseline for the benchmark
+mod legacy {
+    //using mod operator to get remainder. This is to get around shifting by negative values with a usize
+    const DIRECTIONS: [usize; 8] = [19, 18, 17, 11, 1, 7, 8, 9];
+
+    pub fn generate_moves(player: u64, opponent: u64) -> u64 {
+        let empty_squares: u64 = !(player | opponent);
+        let mut moves: u64 = 0;
+        for dir in DIRECTIONS {
+            let mut candidates: u64 = opponent & if dir <= 9 { player >> dir } else { player << (dir % 10) };
+            while candidates != 0 {
+                moves |= empty_squares & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
+                candidates = opponent & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
+            }
+        }
+        moves
+    }
+
+    pub fn compute_flips(player: u64, opponent: u64, selected_move: u64) -> u64 {
+        let mut flip_mask: u64 = 0;
+        for dir in DIRECTIONS {
+            let mut direction_tile = if dir <= 9 { selected_move << dir } else { selected_move >> (dir % 10) } & opponent;
+            let mut current_mask: u64 = 0;
+            while direction_tile != 0 {
+                current_mask |= direction_tile;
+                direction_tile = if dir <= 9 { direction_tile << dir } else { direction_tile >> (dir % 10) };
+                if direction_tile & player != 0 {
+                    flip_mask |= current_mask;
+                    break;
+                } else if direction_tile & opponent == 0 {
+                    direction_tile = 0;
+                }
+            }
+        }
+        flip_mask
+    }
+}
@@ -0,0 +1,118 @@
+//Imports
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
+
+//Modules
+use crate::board::{Board, FlipMethod};
+use crate::constants;
+use crate::flip_tables;
+
+//first and last column of the 10 by 10 grid
+const WIDE_FILE_A: u128 = wide_file(0);
+const WIDE_FILE_J: u128 = wide_file(9);
+
+/// The integer a board keeps one bit per square in. Squares are numbered row by row with STRIDE
+/// squares to a row, a1 being bit 0. Boards smaller than the grid sit in its a1 corner
+pub trait Bitboard: Copy + Eq + fmt::Debug
+    + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+    + BitAndAssign + BitOrAssign + BitXorAssign + Shl<u32, Output = Self> + Shr<u32, Output = Self> {
+    /// Squares in a row of the grid, which is also the largest board that fits
+    const STRIDE: usize;
+    const EMPTY: Self;
+    /// First and last column of the grid, used to stop shifts from wrapping around to the other side
+    const FILE_A: Self;
+    const FILE_LAST: Self;
+    /// Doubling steps a Kogge-Stone fill needs to cross the longest run of pieces on a line
+    const FILL_STEPS: u32;
+
+    ///Returns the bitboard holding only the square with the given index
+    fn square(index: usize) -> Self;
+
+    fn count_ones(self) -> u32;
+
+    fn trailing_zeros(self) -> u32;
+
+    ///Returns the lowest square of the set, or EMPTY if there is none
+    fn lowest_square(self) -> Self;
+
+    ///Returns the pieces flipped by the move from the precomputed line tables, if the bitboard has
+    /// them and they are in use. Otherwise the Kogge-Stone fills are used
+    fn table_flips(_player: Self, _opponent: Self, _selected_move: Self) -> Option<Self> {
+        None
+    }
+
+    ///Returns the shift of each line through a square, used both ways to cover all 8 directions.
+    /// The mask keeps runs of pieces off the first and last file so a shift can never wrap around
+    fn directions() -> [(u32, Self); 4] {
+        let inner = !(Self::FILE_A | Self::FILE_LAST);
+        let stride = Self::STRIDE as u32;
+        [(1, inner), (stride, !Self::EMPTY), (stride - 1, inner), (stride + 1, inner)]
+    }
+}
+
+impl Bitboard for u64 {
+    const STRIDE: usize = constants::BOARD_SIZE;
+    const EMPTY: Self = 0;
+    const FILE_A: Self = constants::FILE_A;
+    const FILE_LAST: Self = constants::FILE_H;
+    const FILL_STEPS: u32 = 3;
+
+    fn square(index: usize) -> Self {
+        1 << index
+    }
+
+    fn count_ones(self) -> u32 {
+        u64::count_ones(self)
+    }
+
+    fn trailing_zeros(self) -> u32 {
+        u64::trailing_zeros(self)
+    }
+
+    fn lowest_square(self) -> Self {
+        self & self.wrapping_neg()
+    }
+
+    fn table_flips(player: Self, opponent: Self, selected_move: Self) -> Option<Self> {
+        match Board::flip_method() {
+            FlipMethod::Table => Some(flip_tables::compute_flips(player, opponent, selected_move)),
+            FlipMethod::Shift => None,
+        }
+    }
+}
+
+/// Holds the 10 by 10 board, which does not fit in 64 bits
+impl Bitboard for u128 {
+    const STRIDE: usize = 10;
+    const EMPTY: Self = 0;
+    const FILE_A: Self = WIDE_FILE_A;
+    const FILE_LAST: Self = WIDE_FILE_J;
+    const FILL_STEPS: u32 = 4;
+
+    fn square(index: usize) -> Self {
+        1 << index
+    }
+
+    fn count_ones(self) -> u32 {
+        u128::count_ones(self)
+    }
+
+    fn trailing_zeros(self) -> u32 {
+        u128::trailing_zeros(self)
+    }
+
+    fn lowest_square(self) -> Self {
+        self & self.wrapping_neg()
+    }
+}
+
+// Every square of one column of the 10 by 10 grid
+const fn wide_file(column: usize) -> u128 {
+    let mut file = 0;
+    let mut row = 0;
+    while row < 10 {
+        file |= 1 << (row * 10 + column);
+        row += 1;
+    }
+    file
+}
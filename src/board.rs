@@ -1,22 +1,49 @@
 //Imports
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 //Modules
 use crate::constants;
-use crate::ai_logic;
-use crate::ai_logic::AI_Logic_Handler;
+use crate::flip_tables;
+use crate::stability;
+use crate::ai_logic::{self, strength, AiLogicHandler, AiType, EngineOptions, MoveAnalysis};
+use crate::ai_logic::personality::Personality;
+use crate::bitboard::Bitboard;
+use crate::clock::{self, Clock, TimeControl};
+use crate::game_result::{EndReason, GameResult};
+use crate::review::GameReview;
+use crate::profile::{AdaptiveStrength, PlayerProfile};
 
 #[derive(Clone)]
-pub struct Board {
-    pub(crate) black: u64,
-    pub(crate) white: u64,
-    pub(crate) current_player: constants::Color
+pub struct Board<B: Bitboard = u64> {
+    pub(crate) black: B,
+    pub(crate) white: B,
+    pub(crate) current_player: constants::Color,
+    // Every square of the board, the rest of the bitboard is never played on
+    pub(crate) squares: B,
+    // The rules that decide who wins
+    pub(crate) variant: constants::Variant
 }
 
-enum Mode{
-    AI,
-    Player
+/// How place_move finds the pieces to flip
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlipMethod {
+    /// Kogge-Stone fills along the 8 directions
+    Shift,
+    /// Lookups in the precomputed line tables
+    Table
 }
+
+// Shared by every board so engines and the game loop always flip the same way
+static USE_FLIP_TABLES: AtomicBool = AtomicBool::new(false);
+
+/// What the user typed when asked for a move
+pub enum UserInput {
+    Move(usize, usize),
+    Hint,
+    Resign
+}
+
 impl Board{
     /// Initializes the variables in Board and returns the object.
     ///
@@ -33,42 +60,200 @@ impl Board{
         let black = constants::STARTING_BLACK;
         let white = constants::STARTING_WHITE;
         let current_player = constants::STARTING_PLAYER;
-        Self {black, white, current_player}
+        Self {black, white, current_player, squares: constants::FULL, variant: constants::Variant::Standard}
     }
 
-    pub fn get_possible_moves(&self) -> u64 {
-        let (player, opponent) = self.get_bitboards();
+    ///Selects how place_move computes flips for every board. Building the tables
+    /// happens here, so it is not charged to the first move of a game
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'method' - The flip computation to use from now on
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// Board::set_flip_method(FlipMethod::Table);
+    /// ```
+    pub fn set_flip_method(method: FlipMethod) {
+        if method == FlipMethod::Table {
+            flip_tables::initialize();
+        }
+        USE_FLIP_TABLES.store(method == FlipMethod::Table, Ordering::Relaxed);
+    }
 
-        let empty_squares: u64 = !(self.black ^ self.white);
-        let mut moves: u64 = 0;
+    ///Returns the flip computation place_move currently uses
+    pub fn flip_method() -> FlipMethod {
+        match USE_FLIP_TABLES.load(Ordering::Relaxed) {
+            true => FlipMethod::Table,
+            false => FlipMethod::Shift,
+        }
+    }
 
-        for dir in constants::DIRECTIONS {
-            let mut candidates: u64 = opponent & if dir <= 9 { player >> dir } else { player << (dir % 10) };
-            while candidates != 0 {
-                moves |= empty_squares & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
-                candidates = opponent & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
+    ///Returns the pieces of the specified player that can never be flipped again
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'color' - The color of pieces to check
+    ///
+    /// # Examples
+    ///
+    ///  ```
+    /// let safe_corners = board.stable_discs(Color::Black) & CORNER_MASK;
+    ///  ```
+    pub fn stable_discs(&self, color: constants::Color) -> u64 {
+        stability::stable_discs(self.get_color_bitboard(color), self.get_color_bitboard(color.opponent()), self.squares)
+    }
+
+    ///Returns the number of the specified player's pieces that can never be flipped again
+    pub fn count_stable(&self, color: constants::Color) -> u32 {
+        self.stable_discs(color).count_ones()
+    }
+}
+
+impl<B: Bitboard> Board<B> {
+    ///Returns a board of the given size in its starting position, or None if the size is odd,
+    /// below MIN_BOARD_SIZE or too big for the bitboard. 4x4, 6x6 and 8x8 boards fit in a u64
+    /// and the 10x10 board needs a u128
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'size' - The number of rows and columns
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let small_board: Board = Board::with_size(6).unwrap();
+    /// let wide_board: Board<u128> = Board::with_size(10).unwrap();
+    /// ```
+    pub fn with_size(size: usize) -> Option<Self> {
+        if size < constants::MIN_BOARD_SIZE || size > B::STRIDE || !size.is_multiple_of(2) {
+            return None;
+        }
+        let square = |row: usize, col: usize| B::square(row * B::STRIDE + col);
+        let mut squares = B::EMPTY;
+        for row in 0..size {
+            for col in 0..size {
+                squares |= square(row, col);
             }
         }
-        moves
+        // The four starting discs sit in the middle, black on the a8 to h1 diagonal
+        let middle = size / 2;
+        let black = square(middle - 1, middle) | square(middle, middle - 1);
+        let white = square(middle - 1, middle - 1) | square(middle, middle);
+        Some(Self {black, white, current_player: constants::STARTING_PLAYER, squares, variant: constants::Variant::Standard})
+    }
+
+    ///Returns the board played by the given rules instead
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'variant' - The rules that decide who wins
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let anti_board = Board::new().with_variant(Variant::Anti);
+    /// ```
+    pub fn with_variant(mut self, variant: constants::Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    ///Returns the number of rows and columns of the board
+    pub fn size(&self) -> usize {
+        (self.squares & B::FILE_A).count_ones() as usize
+    }
+
+    ///Returns the number of squares on the board
+    pub fn count_squares(&self) -> u32 {
+        self.squares.count_ones()
     }
 
-    pub fn get_possible_moves_of_color(&self, color: &constants::Color) -> u64 {
+    pub fn get_possible_moves(&self) -> B {
+        let (player, opponent) = self.get_bitboards();
+        Self::generate_moves(player, opponent) & self.squares
+    }
+
+    pub fn get_possible_moves_of_color(&self, color: &constants::Color) -> B {
         let (player, opponent) = match color {
             constants::Color::Black => (self.black, self.white),
             constants::Color::White => (self.white, self.black)
         };
+        Self::generate_moves(player, opponent) & self.squares
+    }
+
+    ///Returns the legal moves for player against opponent on the full grid. Boards smaller than the
+    /// grid have to mask off the squares past their edges, which get_possible_moves does.
+    /// Each direction is filled with a Kogge-Stone parallel prefix, so a run of opponent
+    /// pieces of any length is found in a few shifts instead of one shift per square.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'player' - The bitboard of the player to move
+    ///  * 'opponent' - The bitboard of the other player
+    pub fn generate_moves(player: B, opponent: B) -> B {
         // generate bitboard of all empty squares
-        let empty_squares: u64 = !(self.black ^ self.white);
-        let mut moves: u64 = 0;
-        //check each direction for legal moves
-        for dir in constants::DIRECTIONS {
-            let mut candidates: u64 = opponent & if dir <= 9 { player >> dir } else { player << (dir % 10) };
-            while candidates != 0 {
-                moves |= empty_squares & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
-                candidates = opponent & if dir <= 9 { candidates >> dir } else { candidates << (dir % 10) };
+        let empty_squares: B = !(player | opponent);
+        let mut moves: B = B::EMPTY;
+        //check both ways along each line for legal moves
+        for (shift, mask) in B::directions() {
+            let propagator = opponent & mask;
+            moves |= (Self::fill_left(player, propagator, shift) & propagator) << shift;
+            moves |= (Self::fill_right(player, propagator, shift) & propagator) >> shift;
+        }
+        moves & empty_squares
+    }
+
+    ///Returns the pieces flipped when player plays on the given square
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'player' - The bitboard of the player to move
+    ///  * 'opponent' - The bitboard of the other player
+    ///  * 'selected_move' - The square being played as a bitboard
+    pub fn compute_flips(player: B, opponent: B, selected_move: B) -> B {
+        let mut flip_mask: B = B::EMPTY;
+        for (shift, mask) in B::directions() {
+            let propagator = opponent & mask;
+            // Run of opponent pieces starting next to the move, only flipped if a player piece closes it off
+            if (selected_move << shift) & propagator != B::EMPTY {
+                let run = Self::fill_left(selected_move, propagator, shift) & propagator;
+                if (run << shift) & player != B::EMPTY {
+                    flip_mask |= run;
+                }
+            }
+            if (selected_move >> shift) & propagator != B::EMPTY {
+                let run = Self::fill_right(selected_move, propagator, shift) & propagator;
+                if (run >> shift) & player != B::EMPTY {
+                    flip_mask |= run;
+                }
             }
         }
-        moves
+        flip_mask
+    }
+
+    // Spreads the generator squares left through every propagator square,
+    // doubling the distance covered with every step
+    fn fill_left(mut generator: B, mut propagator: B, shift: u32) -> B {
+        let mut distance = shift;
+        for _ in 0..B::FILL_STEPS {
+            generator |= propagator & (generator << distance);
+            propagator &= propagator << distance;
+            distance *= 2;
+        }
+        generator
+    }
+
+    // Same as fill_left in the other direction
+    fn fill_right(mut generator: B, mut propagator: B, shift: u32) -> B {
+        let mut distance = shift;
+        for _ in 0..B::FILL_STEPS {
+            generator |= propagator & (generator >> distance);
+            propagator &= propagator >> distance;
+            distance *= 2;
+        }
+        generator
     }
 
     ///Places the current move on the board and handles flipping affected tiles
@@ -88,45 +273,19 @@ impl Board{
     /// let pos = get_pos(row, col);
     /// board.place_piece(moves, pos);
     /// ```
-    pub fn place_move(&mut self, possible_moves: &u64, selected_move: u64) -> bool {
+    pub fn place_move(&mut self, possible_moves: &B, selected_move: B) -> bool {
         //invalid move
-        if (possible_moves & selected_move == 0) || selected_move == 0 {
+        if (*possible_moves & selected_move == B::EMPTY) || selected_move == B::EMPTY {
            return false;
         }
 
         let (mut player, mut opponent) = self.get_bitboards();
-        //place move on corresponding board
-        player |= selected_move;
 
         //find valid tiles to flip
-        let empty_squares: u64 = !(self.black ^ self.white);
-        let mut flip_mask: u64 = 0;
-        for dir in constants::DIRECTIONS {
-            // Get  enemy tile at that direction
-            let mut direction_tile = if dir <= 9 { selected_move << dir } else { selected_move >> (dir % 10) } & opponent;
-            // print_as_board(&direction_tile);// Testing purposes
-            let mut current_mask: u64 = 0;
-            // Loop travel in that direction until hits empty space or player tile
-            // print_as_board(&player);// Testing purposes
-            // print_as_board(&opponent);// Testing purposes
-            while direction_tile != 0 {
-                // Add current bit to
-                current_mask |= direction_tile;
-                direction_tile = if dir <= 9 { direction_tile << dir } else { direction_tile >> (dir % 10) };
-                // print_as_board(&direction_tile); // Testing purposes
-                if direction_tile & player != 0 {
-                    flip_mask |= current_mask;
-                    break;
-                } else if direction_tile & opponent == 0 {
-                    direction_tile = 0;
-                }
-            }
-            if direction_tile & player > 0 {
-                flip_mask |= current_mask;
-            }
-        }
-        //apply flip to both bitboards
-        player ^= flip_mask;
+        let flip_mask = B::table_flips(player, opponent, selected_move)
+            .unwrap_or_else(|| Self::compute_flips(player, opponent, selected_move));
+        //place move on corresponding board and apply flip to both bitboards
+        player |= selected_move | flip_mask;
         opponent ^= flip_mask;
         // Update Active Bitboards and current_player
         match self.current_player {
@@ -153,22 +312,72 @@ impl Board{
     /// # Examples
     ///
     ///  ```
-    /// let black_pieces = board.get_pieces(Color::Black);
+    /// let black_pieces = board.count_discs(Color::Black);
     ///  ```
-    pub fn get_pieces(&self, color: constants::Color) -> u8 {
-        let mut count: u8 = 0;
-        let pieces: u64 = match color {
+    pub fn count_discs(&self, color: constants::Color) -> u32 {
+        self.get_color_bitboard(color).count_ones()
+    }
+
+    ///Returns how many discs the specified player is ahead by under the rules of the board.
+    /// In anti-Othello the player with fewer discs is ahead
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'color' - The color of the player to count for
+    pub fn disc_margin(&self, color: constants::Color) -> i32 {
+        let margin = self.count_discs(color) as i32 - self.count_discs(color.opponent()) as i32;
+        margin * self.variant.disc_sign()
+    }
+
+    ///Returns a bitboard of every empty square
+    pub fn empty_squares(&self) -> B {
+        !(self.black | self.white) & self.squares
+    }
+
+    ///Returns the number of empty squares left on the board
+    pub fn count_empty(&self) -> u32 {
+        self.empty_squares().count_ones()
+    }
+
+    ///Returns the number of legal moves the specified player would have if it was their turn
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'color' - The color of the player to count moves for
+    pub fn count_mobility(&self, color: constants::Color) -> u32 {
+        self.get_possible_moves_of_color(&color).count_ones()
+    }
+
+    ///Returns the number of the specified player's pieces that touch an empty square.
+    /// Frontier pieces are the ones that give the opponent moves
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'color' - The color of pieces to count
+    pub fn count_frontier(&self, color: constants::Color) -> u32 {
+        (self.get_color_bitboard(color) & Self::adjacent_squares(self.empty_squares())).count_ones()
+    }
+
+    ///Returns the bitboard of the pieces owned by the specified player
+    pub fn get_color_bitboard(&self, color: constants::Color) -> B {
+        match color {
             constants::Color::Black => self.black,
             constants::Color::White => self.white,
-        };
-
-        for i in 1..64 {
-            let bit: u64 = pieces & (1 << i);
-            if bit > 0 {
-                count += 1;
-            }
         }
-        count
+    }
+
+    ///Returns every square that neighbours one of the given squares in any of the 8 directions.
+    /// Shifts that would wrap around the first or last file are masked off
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'squares' - The bitboard of squares to find the neighbours of
+    pub fn adjacent_squares(squares: B) -> B {
+        let west = (squares >> 1) & !B::FILE_LAST;
+        let east = (squares << 1) & !B::FILE_A;
+        let row = squares | west | east;
+        let stride = B::STRIDE as u32;
+        (west | east | (row << stride) | (row >> stride)) & !squares
     }
     ///Returns the bitboards in current player / opponent order
     ///
@@ -179,7 +388,7 @@ impl Board{
     ///  ```
     /// let (player, opponent) = board.get_bitboards();
     ///  ```
-    fn get_bitboards(&self) -> (u64, u64) {
+    fn get_bitboards(&self) -> (B, B) {
         //return the bitboards with the current player first
         match self.current_player {
             constants::Color::Black => (self.black, self.white),
@@ -187,6 +396,12 @@ impl Board{
         }
     }
 
+    ///Gives the turn to the other player without placing a piece.
+    /// Only legal when the current player has no moves
+    pub fn pass_turn(&mut self) {
+        self.current_player = self.current_player.opponent();
+    }
+
     // ----- Helper Functions ----- //
 
     ///Prints the current game-state of the board given.
@@ -204,27 +419,28 @@ impl Board{
     /// let moves = board.get_possible_moves();
     /// print_board(board, moves);
     /// ```
-    pub fn print_board(board: &Board, moves: &u64) {
-        print!("X\tA\tB\tC\tD\tE\tF\tG\tH\n1\t");
-        for i in 0..64 {
-            // print!("{}", i); // Used for testing.
-            let white_bit = board.white & (1 << i);
-            let black_bit = board.black & (1 << i);
-            if white_bit >= 1 {
-                print!("W\t");
-            } else if black_bit >= 1 {
-                print!("B\t");
-            } else if moves & (1 << i) > 0 {
-                print!("X\t");
-            } else {
-                print!("-\t");
+    pub fn print_board(board: &Board<B>, moves: &B) {
+        let size = board.size();
+        Self::print_header(size);
+        for row in 0..size {
+            print!("{}\t", row + 1);
+            for col in 0..size {
+                let square = B::square(row * B::STRIDE + col);
+                if board.white & square != B::EMPTY {
+                    print!("W\t");
+                } else if board.black & square != B::EMPTY {
+                    print!("B\t");
+                } else if *moves & square != B::EMPTY {
+                    print!("X\t");
+                } else {
+                    print!("-\t");
+                }
             }
-
-            if i % 8 == 7 && i > 0 && i < 60 {
-                print!("\n{}\t", i / 8 + 2);
+            if row + 1 < size {
+                println!();
             }
         }
-        print!("\nBlack Pieces: {}\tWhite Pieces {}\n", board.get_pieces(constants::Color::Black), board.get_pieces(constants::Color::White))
+        print!("\nBlack Pieces: {}\tWhite Pieces {}\n", board.count_discs(constants::Color::Black), board.count_discs(constants::Color::White))
     }
 
 
@@ -235,23 +451,25 @@ impl Board{
     ///  # Arguments
     ///
     ///  * 'board' - The bitboard containing the values to print
-    pub fn print_as_board(board: &u64) {
-        print!("X\tA\tB\tC\tD\tE\tF\tG\tH\n1\t");
-        for i in 0..64 {
-            // print!("{}", i); // Used for testing.
-
-            let bit = board & (1 << i);
-            if bit >= 1 {
-                print!("X\t");
-            } else {
-                print!("-\t");
-            }
-
-            if i % 8 == 7 && i > 0 && i < 60 {
-                print!("\n{}\t", i / 8 + 2);
+    #[allow(dead_code)]
+    pub fn print_as_board(board: &B) {
+        Self::print_header(B::STRIDE);
+        for row in 0..B::STRIDE {
+            print!("{}\t", row + 1);
+            for col in 0..B::STRIDE {
+                match *board & B::square(row * B::STRIDE + col) != B::EMPTY {
+                    true => print!("X\t"),
+                    false => print!("-\t"),
+                }
             }
+            println!();
         }
-        print!("\n")
+    }
+
+    // The column letters above a board of the given size
+    fn print_header(size: usize) {
+        let columns: Vec<String> = (0..size as u8).map(|col| ((col + b'A') as char).to_string()).collect();
+        println!("X\t{}", columns.join("\t"));
     }
 
     /// Takes in the row and column of a move and converts it to a bitboard position
@@ -265,25 +483,24 @@ impl Board{
     ///
     /// ```
     /// let (row, col) = get_input("What move do you want to make?");
-    /// let pos = match got_pos(row, col) {
+    /// let pos = match game_board.get_pos(row, col) {
     ///     Ok(value) => value,
     ///     Err(error) => print!(error),
     /// }
     /// ```
-    pub fn get_pos(row: usize, col: usize) -> Result<u64, u64> {
-        if (row < 0 || row > 7) || (col < 0 || col > 7) {
-            return Err(u64::MAX);
+    pub fn get_pos(&self, row: usize, col: usize) -> Result<B, B> {
+        if row >= self.size() || col >= self.size() {
+            return Err(!B::EMPTY);
         };
-        Ok(1 << (row * constants::BOARD_SIZE + col) as u64)
+        Ok(B::square(row * B::STRIDE + col))
     }
 
-    pub fn convert_to_cords(bitboard: u64) -> String{
+    pub fn convert_to_cords(bitboard: B) -> String{
         let move_index = bitboard.trailing_zeros() as usize;
-        let row = (move_index / 8) as u8;
-        let col = (move_index % 8) as u8;
+        let row = move_index / B::STRIDE;
+        let col = (move_index % B::STRIDE) as u8;
         let col_char = (col + b'a') as char;
-        let row_char = (row + b'1') as char;
-        format!("{} {}", col_char, row_char)
+        format!("{} {}", col_char, row + 1)
     }
 
     ///Checks whether win conditions have been met. If so, return true, else false
@@ -298,32 +515,51 @@ impl Board{
     ///     break;
     /// }
     ///  ```
-    pub fn check_game_over(board: &Board) -> bool {
+    pub fn check_game_over(board: &Board<B>) -> bool {
+        board.game_over_reason().is_some()
+    }
+
+    ///Returns why the game is over, or None if it is still going
+    ///
+    ///  # Examples
+    ///
+    ///  ```
+    /// if let Some(reason) = game_board.game_over_reason() {
+    ///     break GameResult::from_board(&game_board, reason);
+    /// }
+    ///  ```
+    pub fn game_over_reason(&self) -> Option<EndReason> {
         //if no empty pieces on board or black has no pieces or white has no pieces, end game
-        let black_pieces = board.get_pieces(constants::Color::Black);
-        let white_pieces = board.get_pieces(constants::Color::White);
-        if !(board.black ^ board.white) == 0 || black_pieces == 0 || white_pieces == 0 {
-            return true;
+        if self.black | self.white == self.squares {
+            return Some(EndReason::BoardFull);
+        }
+        if self.black == B::EMPTY || self.white == B::EMPTY {
+            return Some(EndReason::WipeOut);
         }
 
-        if board.get_possible_moves_of_color(&constants::Color::Black) == 0 && board.get_possible_moves_of_color(&constants::Color::White) == 0 {
-            return true;
+        if self.get_possible_moves_of_color(&constants::Color::Black) == B::EMPTY && self.get_possible_moves_of_color(&constants::Color::White) == B::EMPTY {
+            return Some(EndReason::BothPassed);
         }
 
-        false
+        None
     }
+}
 
+impl Board {
     pub fn get_input(prompt: String, ) -> String {
+        Board::get_raw_input(prompt).to_uppercase()
+    }
+
+    /// Same as get_input, but keeps the case of the answer. Used for file names
+    pub fn get_raw_input(prompt: String) -> String {
         let mut input = String::from("");
-        loop {
-            print!("{}\n", prompt);
-            io::stdin().read_line(&mut input).expect("Failed to read line");
-            return input.trim().to_uppercase();
-        }
+        println!("{}", prompt);
+        io::stdin().read_line(&mut input).expect("Failed to read line");
+        input.trim().to_string()
     }
 
     /// Gets desired move from user as a string and converts it to
-    /// two unsigned 64 bit integers.
+    /// two unsigned 64 bit integers. Typing 'hint' asks the engine for advice instead, and 'resign' gives up the game.
     ///
     ///  # Arguments
     ///
@@ -332,18 +568,26 @@ impl Board{
     ///  # Examples
     ///
     /// ```
-    /// let (row, col) = get_input("What move do you want to make?");
+    /// if let UserInput::Move(row, col) = Board::get_user_move() {
+    ///     let pos = game_board.get_pos(row, col);
+    /// }
     ///```
-    pub fn get_user_move() -> (usize, usize) {
-        let prompt:String = String::from("Please input your move: (column-letter, row-number)");
+    pub fn get_user_move() -> UserInput {
+        let prompt:String = String::from("Please input your move: (column-letter, row-number), 'hint' or 'resign'");
         let mut input = String::from("");
         let mut col: usize;
-        let mut row: usize;
+        let row: usize;
         // Get and parse move from player
         loop {
-            print!("{}\n", prompt);
+            println!("{}", prompt);
             io::stdin().read_line(&mut input).expect("Failed to read line");
             input = input.trim().to_uppercase();
+            if input == "HINT" {
+                return UserInput::Hint;
+            }
+            if input == "RESIGN" {
+                return UserInput::Resign;
+            }
 
             let (col_char, row_str) = input.split_at(1);
             // Convert column character to integer value
@@ -356,8 +600,10 @@ impl Board{
                 "F" => 5,
                 "G" => 6,
                 "H" => 7,
+                "I" => 8,
+                "J" => 9,
                 _ => {
-                    print!("Invalid Column\n");
+                    println!("Invalid Column");
                     input = String::from("");
                     continue;
                 }
@@ -366,98 +612,535 @@ impl Board{
             row = match row_str.trim().parse::<usize>() {
                 Ok(n) => n - 1,
                 Err(_) => {
-                    print!("Invalid Row\n");
+                    println!("Invalid Row");
                     input = String::from("");
                     continue;
                 }
             };
-            return (row, col);
+            return UserInput::Move(row, col);
         }
         }
+
+    ///Prints the engine's analysis of every legal move, best move first
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'analysis' - The ranked moves returned by the engine
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let analysis = ai_handler.analyze(game_board.clone(), constants::HINT_TIME);
+    /// Board::print_analysis(&analysis);
+    /// ```
+    pub fn print_analysis(analysis: &[MoveAnalysis]) {
+        println!("Rank\tMove\tScore\tResult\t\tLine");
+        for (rank, entry) in analysis.iter().enumerate() {
+            let verdict = match entry.verdict {
+                Some(verdict) => verdict.to_string(),
+                None => String::from("-"),
+            };
+            let line: Vec<String> = entry.principal_variation.iter()
+                .take(constants::HINT_PV_LENGTH)
+                .map(|&pv_move| Board::convert_to_cords(pv_move))
+                .collect();
+            println!("{}\t{}\t{}\t{:<8}\t{}", rank + 1, Board::convert_to_cords(entry.chosen_move), entry.score, verdict, line.join(", "));
+        }
+    }
     }
 
-    pub fn run_game() {
+    ///Plays a game between the user and the AI. Every random choice of the AI comes from the seed,
+    /// so the same seed and the same moves by the user replay the game with the random player and
+    /// the bots. The searching engines think for a time, so their moves can differ between runs
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'options' - The engine settings
+    ///  * 'variant' - The rules given with --variant, the user is asked for them if there are none
+    ///  * 'seed' - The seed every random choice of the AI comes from
+    pub fn run_game(options: EngineOptions, variant: Option<constants::Variant>, seed: u64) -> GameResult {
         // Define and initialize objects
 
         // Define Board
-        let mut game_board: Board = Board::new();
+        let mut game_board = starting_board(get_board_size(), variant);
         // Define Player Color
-        let player_return_type = Board::get_input(String::from("What color do you want to play?"));
-        let player: constants::Color = match player_return_type.to_lowercase().as_str() {
-            "black" => constants::Color::Black,
-            "white" => constants::Color::White,
-            _ => constants::Color::Black,
-        };
+        let player = get_player_color();
 
         // Define AI difficulty
-        let difficulty_string = Board::get_input(String::from("What difficulty do you want to play? (easy, normal, hard)"));
+        let difficulty_string = Board::get_input(format!("What difficulty do you want to play? (easy, normal, hard, adaptive, a level from 1 to {}, or an engine: {})", strength::LEVELS.len(), ai_logic::ENGINE_NAMES));
+        let mut profile = PlayerProfile::load();
         let difficulty = match difficulty_string.to_lowercase().as_str() {
             "easy" => constants::Difficulty::Easy, // random moves
             "normal" => constants::Difficulty::Normal, // minimax algorithm
             "hard" => constants::Difficulty::Hard, //monte carlo tree search
-            _ => constants::Difficulty::Normal,
+            "adaptive" => constants::Difficulty::Adaptive(profile.level), // limited minimax that follows the player
+            name => match (name.parse::<usize>(), AiType::from_name(name)) {
+                (Ok(number), _) if strength::level(number).is_some() => constants::Difficulty::Level(number), // limited minimax
+                (_, Some(engine)) => constants::Difficulty::Engine(engine), // any engine by name
+                _ => constants::Difficulty::Normal,
+            },
         };
 
-        let ai_color = match player {
-            constants::Color::Black => constants::Color::White,
-            constants::Color::White => constants::Color:: Black,
+        // The adaptive mode measures every move of the player to pick the engine's level
+        let mut adaptive = match difficulty {
+            constants::Difficulty::Adaptive(level) => {
+                println!("Adaptive mode starts at level {} ({} games played, accuracy {:.1}%)", level, profile.games(), profile.accuracy);
+                Some(AdaptiveStrength::new(level))
+            }
+            _ => None,
         };
-        let ai_handler= AI_Logic_Handler::new(ai_color, difficulty);
+        let ai_color = player.opponent();
+        let mut ai_handler= AiLogicHandler::new(ai_color, difficulty, options, seed);
+        println!("Game seed: {} (replay with --seed {})", seed, seed);
 
+        // Define time control, both players get the same clock
+        let time_control = get_time_control();
+        let mut black_clock = Clock::new(time_control);
+        let mut white_clock = Clock::new(time_control);
+        // Every position of the game with the move played in it, for the post-game review
+        let mut history: Vec<(Board, u64)> = Vec::new();
 
         // Game loop
-        loop {
-            let mut played_move: bool;
-
+        let result = loop {
             let possible_moves = game_board.get_possible_moves();
             // Print Current Board
             Board::print_board(&game_board, &possible_moves);
-            // If it is blacks turn
-            match game_board.current_player {
-                constants::Color::Black =>
-                    {
-                        match player {
-                        constants::Color::Black => {
-                            // Get move
-                            let (move_row, move_col) = Board::get_user_move();
-                            let player_move = Board::get_pos(move_row, move_col);
-                            // Try to play move
-                            played_move = match player_move {
-                                Ok(value) => game_board.place_move(&possible_moves, value),
-                                Err(value) => false,
-                            };
-                        }
-                            constants::Color::White => {
-                               let ai_move =  ai_handler.get_ai_move(&possible_moves, game_board.clone());
-                                played_move = game_board.place_move(&possible_moves, ai_move);
-                            }
-
-
-                        }
+            match (ai_handler.level(), &adaptive) {
+                (Some(level), Some(_)) => println!("Engine level: {} of {} (adaptive)", level, strength::LEVELS.len()),
+                (Some(level), None) => println!("Engine level: {} of {}", level, strength::LEVELS.len()),
+                _ => {}
+            }
+            if options.personality != Personality::Balanced {
+                println!("Engine personality: {}", options.personality);
+            }
+            if black_clock.is_timed() {
+                println!("Black Clock: {}\tWhite Clock: {}", clock::format_time(black_clock.remaining()), clock::format_time(white_clock.remaining()));
+            }
+            let mover = game_board.current_player;
+            // The game is not over, so the opponent can move after a pass
+            if possible_moves == 0 {
+                println!("{} has no legal moves and passes", mover);
+                game_board.pass_turn();
+                continue;
+            }
+            let mover_clock = match mover {
+                constants::Color::Black => &mut black_clock,
+                constants::Color::White => &mut white_clock,
+            };
+            mover_clock.start_turn();
+            let position = game_board.clone();
+            let selected_move = if mover == player {
+                // Get move
+                match Board::get_user_move() {
+                    UserInput::Move(move_row, move_col) => {
+                        // Invalid positions are never legal moves
+                        game_board.get_pos(move_row, move_col).unwrap_or(0)
                     }
-                constants::Color::White => {
-                    match player {
-                        constants::Color::White => {
-                            let (move_row, move_col) = Board::get_user_move();
-                            let player_move = Board::get_pos(move_row, move_col);
-                            // Try to play move
-                            played_move = match player_move {
-                                Ok(value) => game_board.place_move(&possible_moves, value),
-                                Err(value) => false,
-                            };
-
-                        }
-                        constants::Color::Black => {
-                            let ai_move =  ai_handler.get_ai_move(&possible_moves, game_board.clone());
-                           played_move = game_board.place_move(&possible_moves, ai_move);
+                    UserInput::Hint => {
+                        // The hint is thought about on the player's own clock
+                        println!("Analyzing with minimax for {}s...", constants::HINT_TIME.as_secs());
+                        if *ai_handler.engine() != AiType::Minimax {
+                            println!("The hint is the minimax engine's ranking, the {} engine you play against does not rank moves", ai_handler.engine());
                         }
+                        let analysis = ai_handler.analyze(game_board.clone(), constants::HINT_TIME);
+                        Board::print_analysis(&analysis);
+                        0
+                    }
+                    UserInput::Resign => {
+                        println!("{} resigns.", mover);
+                        break GameResult::resignation(&game_board, mover);
                     }
                 }
+            } else {
+                let empty_squares = game_board.count_empty();
+                let time_budget = mover_clock.allocate(empty_squares);
+                ai_handler.get_ai_move(&possible_moves, game_board.clone(), time_budget)
+            };
+            // Try to play move
+            let played_move = game_board.place_move(&possible_moves, selected_move);
+            if played_move {
+                history.push((position.clone(), selected_move));
+            }
+            // A player who runs out of time loses, even if the move came in afterwards
+            if mover_clock.is_flagged() || (played_move && !mover_clock.stop_turn()) {
+                println!("{} ran out of time.", mover);
+                break GameResult::timeout(&game_board, mover);
+            }
+            if let (Some(adaptive), true) = (&mut adaptive, played_move && mover == player) {
+                if adaptive.record_move(ai_handler.move_loss(&position, selected_move)) {
+                    println!("The engine moves to level {}", adaptive.level());
+                    ai_handler.set_level(adaptive.level());
+                }
+            }
+            if let Some(reason) = game_board.game_over_reason() {
+                break GameResult::from_board(&game_board, reason);
+            }
+        };
+
+        // Announce the winner
+        Board::print_board(&game_board, &0);
+        println!("{}", result);
+        println!("Game seed: {}", seed);
+        if let Some(adaptive) = &adaptive {
+            let next_level = profile.record_game(player, &result, adaptive.level(), adaptive.accuracy());
+            println!("Your accuracy this game was {:.1}%, the next adaptive game starts at level {}", adaptive.accuracy(), next_level);
+            if let Err(error) = profile.save() {
+                println!("Could not save the player profile: {}", error);
             }
-            if Board::check_game_over(&game_board) {
-                break;
+        }
+
+        // Post-game review
+        let review_answer = Board::get_input(String::from("Do you want a review of the game? (yes, no)"));
+        if review_answer.to_lowercase() == "yes" {
+            let review = GameReview::analyze(&mut ai_handler, &history, constants::REVIEW_TIME_PER_POSITION, seed);
+            print!("{}", review.transcript());
+            let path = Board::get_raw_input(String::from("Enter a file name to save the review, or leave blank to skip"));
+            if !path.is_empty() {
+                match review.export(&path) {
+                    Ok(()) => println!("Review saved to {}", path),
+                    Err(error) => println!("Could not save the review: {}", error),
+                }
             }
         }
+        result
 
 }
 
+    /// Asks the user which board size to play on. Falls back to 8x8 if the answer could not be understood.
+    fn get_board_size() -> usize {
+        let sizes: Vec<String> = constants::BOARD_SIZES.iter().map(|size| size.to_string()).collect();
+        let answer = Board::get_input(format!("What board size do you want to play? ({})", sizes.join(", ")));
+        // "6" and "6x6" both pick the 6x6 board
+        let rows = answer.to_lowercase().split('x').next().unwrap_or("").trim().parse::<usize>();
+        match rows {
+            Ok(size) if constants::BOARD_SIZES.contains(&size) => size,
+            _ if answer.is_empty() => constants::BOARD_SIZE,
+            _ => {
+                println!("Invalid board size, playing on {}x{}", constants::BOARD_SIZE, constants::BOARD_SIZE);
+                constants::BOARD_SIZE
+            }
+        }
+    }
+
+    ///Returns the starting board of a game of the given size. The user is only asked for the rules
+    /// if they were not given with --variant
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'size' - The number of rows and columns, one of BOARD_SIZES
+    ///  * 'variant' - The rules given on the command line, if any
+    fn starting_board(size: usize, variant: Option<constants::Variant>) -> Board {
+        let variant = variant.unwrap_or_else(get_variant);
+        if variant == constants::Variant::Anti {
+            println!("Anti-Othello: the player with the fewest discs wins");
+        }
+        Board::with_size(size).expect("board sizes come from BOARD_SIZES").with_variant(variant)
+    }
+
+    /// Asks the user which rules to play by. Anything but anti plays the standard game
+    fn get_variant() -> constants::Variant {
+        let answer = Board::get_input(String::from("Which rules do you want to play? (standard, anti)"));
+        constants::Variant::from_name(&answer).unwrap_or(constants::Variant::Standard)
+    }
+
+    /// Asks the user which color to play. Anything but white plays black
+    fn get_player_color() -> constants::Color {
+        let player_return_type = Board::get_input(String::from("What color do you want to play?"));
+        match player_return_type.to_lowercase().as_str() {
+            "black" => constants::Color::Black,
+            "white" => constants::Color::White,
+            _ => constants::Color::Black,
+        }
+    }
+
+    /// Asks the user which time control to play with and parses the answer.
+    /// Falls back to no clock if the answer could not be understood.
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let time_control = get_time_control();
+    /// let mut black_clock = Clock::new(time_control);
+    /// ```
+    fn get_time_control() -> TimeControl {
+        let kind = Board::get_input(String::from("What time control do you want to play? (none, sudden, increment, fixed)"));
+        let (main_time, increment) = match kind.to_lowercase().as_str() {
+            "sudden" => (Board::get_input(String::from("How many minutes per player?")), String::new()),
+            "increment" => (
+                Board::get_input(String::from("How many minutes per player?")),
+                Board::get_input(String::from("How many seconds of increment per move?")),
+            ),
+            "fixed" => (Board::get_input(String::from("How many seconds per move?")), String::new()),
+            _ => (String::new(), String::new()),
+        };
+        match TimeControl::parse(&kind, &main_time, &increment) {
+            Some(time_control) => time_control,
+            None => {
+                println!("Invalid time control, playing without a clock");
+                TimeControl::Unlimited
+            }
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a1 is bit 0 and h8 is bit 63
+    const A1: u64 = 1;
+    const H1: u64 = 1 << 7;
+    const A8: u64 = 1 << 56;
+    const H8: u64 = 1 << 63;
+
+    fn board_with(black: u64, white: u64) -> Board {
+        Board { black, white, current_player: constants::Color::Black, squares: constants::FULL, variant: constants::Variant::Standard }
+    }
+
+    #[test]
+    fn counts_starting_position() {
+        let board = Board::new();
+        assert_eq!(board.count_discs(constants::Color::Black), 2);
+        assert_eq!(board.count_discs(constants::Color::White), 2);
+        assert_eq!(board.count_empty(), 60);
+        assert_eq!(board.count_mobility(constants::Color::Black), 4);
+        assert_eq!(board.count_mobility(constants::Color::White), 4);
+        assert_eq!(board.count_frontier(constants::Color::Black), 2);
+    }
+
+    #[test]
+    fn counts_every_corner() {
+        let board = board_with(A1 | H8, H1 | A8);
+        assert_eq!(board.count_discs(constants::Color::Black), 2);
+        assert_eq!(board.count_discs(constants::Color::White), 2);
+        assert_eq!(board.count_empty(), 60);
+    }
+
+    #[test]
+    fn counts_a1_alone() {
+        let board = board_with(A1, 0);
+        assert_eq!(board.count_discs(constants::Color::Black), 1);
+        assert_eq!(board.count_discs(constants::Color::White), 0);
+        assert_eq!(board.count_empty(), 63);
+    }
+
+    #[test]
+    fn counts_full_board() {
+        let board = board_with(constants::FILE_A, !constants::FILE_A);
+        assert_eq!(board.count_discs(constants::Color::Black), 8);
+        assert_eq!(board.count_discs(constants::Color::White), 56);
+        assert_eq!(board.count_empty(), 0);
+        assert_eq!(board.count_frontier(constants::Color::Black), 0);
+        assert_eq!(board.count_mobility(constants::Color::White), 0);
+    }
+
+    #[test]
+    fn frontier_does_not_wrap_around_the_edges() {
+        // h1 and a2 are next to each other in bit order, but not on the board
+        let board = board_with(H1, !(H1 | (1 << 8)));
+        assert_eq!(board.count_frontier(constants::Color::Black), 0);
+        assert_eq!(board.count_frontier(constants::Color::White), 5);
+    }
+
+    #[test]
+    fn moves_do_not_wrap_around_the_edges() {
+        // g1 black and h1 white, the square after h1 in bit order is a2 which is not on the same row
+        let board = board_with(1 << 6, H1);
+        assert_eq!(board.get_possible_moves_of_color(&constants::Color::Black), 0);
+        // h2 black and a3 white, wrapping the other way
+        let board = board_with(1 << 15, 1 << 16);
+        assert_eq!(board.get_possible_moves_of_color(&constants::Color::Black), 0);
+    }
+
+    #[test]
+    fn flips_every_direction_from_corner() {
+        // Black plays a1 with white runs along the row, the column and the diagonal
+        let white = (1 << 1) | (1 << 2) | (1 << 8) | (1 << 9) | (1 << 18);
+        let black = (1 << 3) | (1 << 16) | (1 << 27);
+        assert_eq!(Board::compute_flips(black, white, A1), white);
+        // With no closing piece on the row, the row is not flipped
+        assert_eq!(Board::compute_flips(black & !(1 << 3), white, A1), white & !((1 << 1) | (1 << 2)));
+    }
+
+    #[test]
+    fn flip_tables_match_shift_flips() {
+        // Play out a few games, checking every legal move of every position
+        for seed in 0..8u64 {
+            let mut board = Board::new();
+            let mut step = seed;
+            while !Board::check_game_over(&board) {
+                let (player, opponent) = board.get_bitboards();
+                let moves = board.get_possible_moves();
+                if moves == 0 {
+                    board.pass_turn();
+                    continue;
+                }
+                let mut remaining = moves;
+                while remaining != 0 {
+                    let current_move = remaining & remaining.wrapping_neg();
+                    remaining ^= current_move;
+                    assert_eq!(flip_tables::compute_flips(player, opponent, current_move), Board::compute_flips(player, opponent, current_move));
+                }
+                // Pick a move spread out by the seed
+                step = step.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let mut chosen = moves;
+                for _ in 0..(step >> 33) % moves.count_ones() as u64 {
+                    chosen &= chosen - 1;
+                }
+                board.place_move(&moves, chosen & chosen.wrapping_neg());
+            }
+        }
+    }
+
+    #[test]
+    fn lone_corner_is_stable() {
+        let board = board_with(A1, 1 << 9);
+        assert_eq!(board.stable_discs(constants::Color::Black), A1);
+        assert_eq!(board.stable_discs(constants::Color::White), 0);
+    }
+
+    #[test]
+    fn starting_position_has_no_stable_discs() {
+        let board = Board::new();
+        assert_eq!(board.stable_discs(constants::Color::Black), 0);
+        assert_eq!(board.stable_discs(constants::Color::White), 0);
+    }
+
+    #[test]
+    fn corner_anchored_edge_is_stable() {
+        // Black on a1 to d1 with white on e1, the white disc can still be flipped from f1
+        let board = board_with(0x0f, 0x10);
+        assert_eq!(board.stable_discs(constants::Color::Black), 0x0f);
+        assert_eq!(board.stable_discs(constants::Color::White), 0);
+    }
+
+    #[test]
+    fn full_board_is_stable() {
+        let board = board_with(constants::FILE_A | (1 << 20), !(constants::FILE_A | (1 << 20)));
+        assert_eq!(board.stable_discs(constants::Color::Black), constants::FILE_A | (1 << 20));
+        assert_eq!(board.count_stable(constants::Color::White), 55);
+    }
+
+    #[test]
+    fn far_corners_of_small_boards_are_stable() {
+        // f6 on the 6x6 board and d4 on the 4x4 board have no squares past them
+        for (size, corner) in [(6, 1u64 << 45), (4, 1 << 27)] {
+            let board = Board { black: corner, white: 0, ..Board::with_size(size).unwrap() };
+            assert_eq!(board.stable_discs(constants::Color::Black), corner);
+        }
+        // White on d1 to f1 can not be flipped once a1 to c1 are taken, the squares past f1 are off the board
+        let board = Board { black: 0x07, white: 0x38, ..Board::with_size(6).unwrap() };
+        assert_eq!(board.stable_discs(constants::Color::White), 0x38);
+    }
+
+    #[test]
+    fn stable_discs_never_flip() {
+        for (size, seed) in [4, 6, 8].into_iter().flat_map(|size| (0..16u64).map(move |seed| (size, seed))) {
+            let mut board: Board = Board::with_size(size).unwrap();
+            let mut step = seed;
+            let mut stable = (0u64, 0u64);
+            while !Board::check_game_over(&board) {
+                // Discs proven stable earlier must still be there with the same color
+                assert_eq!(board.black & stable.0, stable.0);
+                assert_eq!(board.white & stable.1, stable.1);
+                stable = (board.stable_discs(constants::Color::Black), board.stable_discs(constants::Color::White));
+                let moves = board.get_possible_moves();
+                if moves == 0 {
+                    board.pass_turn();
+                    continue;
+                }
+                step = step.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let mut chosen = moves;
+                for _ in 0..(step >> 33) % moves.count_ones() as u64 {
+                    chosen &= chosen - 1;
+                }
+                board.place_move(&moves, chosen & chosen.wrapping_neg());
+            }
+        }
+    }
+
+    #[test]
+    fn mobility_into_corner() {
+        // Black on c1 and white on b1 gives black the a1 corner
+        let board = board_with(1 << 2, 1 << 1);
+        assert_eq!(board.get_possible_moves_of_color(&constants::Color::Black), A1);
+        assert_eq!(board.count_mobility(constants::Color::Black), 1);
+    }
+
+    // Leaf positions after the given number of plies, a pass counting as a ply
+    fn perft<B: Bitboard>(board: &Board<B>, depth: usize) -> u64 {
+        if depth == 0 || Board::check_game_over(board) {
+            return 1;
+        }
+        let moves = board.get_possible_moves();
+        if moves == B::EMPTY {
+            let mut passed = board.clone();
+            passed.pass_turn();
+            return perft(&passed, depth - 1);
+        }
+        let mut nodes = 0;
+        let mut remaining = moves;
+        while remaining != B::EMPTY {
+            let current_move = remaining.lowest_square();
+            remaining ^= current_move;
+            let mut next = board.clone();
+            next.place_move(&moves, current_move);
+            nodes += perft(&next, depth - 1);
+        }
+        nodes
+    }
+
+    #[test]
+    fn perft_matches_every_board_size() {
+        assert_eq!(perft(&Board::<u64>::with_size(4).unwrap(), 7), 3624);
+        assert_eq!(perft(&Board::<u64>::with_size(6).unwrap(), 6), 7604);
+        assert_eq!(perft(&Board::new(), 6), 8200);
+        assert_eq!(perft(&Board::<u128>::with_size(10).unwrap(), 7), 55180);
+    }
+
+    #[test]
+    fn boards_come_in_even_sizes_that_fit() {
+        let small: Board = Board::with_size(6).unwrap();
+        assert_eq!((small.size(), small.count_squares(), small.count_empty()), (6, 36, 32));
+        assert_eq!(small.count_mobility(constants::Color::Black), 4);
+        assert_eq!(small.get_pos(5, 5), Ok(1 << 45));
+        assert!(small.get_pos(6, 0).is_err());
+        assert_eq!(Board::<u64>::with_size(8).map(|board| (board.black, board.white)), Some((constants::STARTING_BLACK, constants::STARTING_WHITE)));
+        assert!(Board::<u64>::with_size(10).is_none());
+        assert!(Board::<u64>::with_size(5).is_none());
+        assert!(Board::<u128>::with_size(12).is_none());
+        assert_eq!(Board::<u128>::convert_to_cords(1 << 99), "j 10");
+    }
+
+    #[test]
+    fn small_board_games_stay_on_the_board() {
+        let mut board: Board = Board::with_size(4).unwrap();
+        while !Board::check_game_over(&board) {
+            let moves = board.get_possible_moves();
+            assert_eq!(moves & !board.squares, 0);
+            if moves == 0 {
+                board.pass_turn();
+                continue;
+            }
+            board.place_move(&moves, moves & moves.wrapping_neg());
+        }
+        assert_eq!((board.black | board.white) & !board.squares, 0);
+        assert!(board.count_discs(constants::Color::Black) + board.count_discs(constants::Color::White) <= 16);
+    }
+
+    #[test]
+    fn the_variant_flag_reaches_the_board_without_a_prompt() {
+        let board: Board = starting_board(6, Some(constants::Variant::Anti));
+        assert_eq!((board.size(), board.variant), (6, constants::Variant::Anti));
+    }
+
+    #[test]
+    fn wide_board_flips_long_runs_without_wrapping() {
+        // Black on a1 and white on b1 to i1 gives black j1, turning all eight white discs
+        let white: u128 = 0x1fe;
+        let board = Board::<u128> { black: 1, white, current_player: constants::Color::Black, ..Board::<u128>::with_size(10).unwrap() };
+        assert_eq!(board.get_possible_moves(), 1 << 9);
+        assert_eq!(Board::compute_flips(1, white, 1 << 9), white);
+        // White on j1 can not reach a2 on the next row
+        assert_eq!(Board::<u128>::generate_moves(1 << 8, 1 << 9) & (1 << 10), 0);
+    }
+}
@@ -0,0 +1,268 @@
+//Imports
+use std::time::{Duration, Instant};
+
+//Modules
+use crate::constants;
+
+/// The time control a game is played under.
+#[derive(Clone, Copy)]
+pub enum TimeControl {
+    /// No clock. The AI thinks for `DEFAULT_AI_MOVE_TIME` every move
+    Unlimited,
+    /// A single bank of time for the whole game
+    SuddenDeath { total: Duration },
+    /// A bank of time that grows by `increment` after every move (Fischer)
+    Increment { total: Duration, increment: Duration },
+    /// A fixed amount of time for every move that does not carry over
+    FixedPerMove { per_move: Duration },
+}
+
+impl TimeControl {
+    ///Parses the time control from the answers given at the start of the game.
+    /// Returns None if the answers could not be understood
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'kind' - The name of the time control (none, sudden, increment, fixed)
+    ///  * 'minutes' - The main time in minutes, or the seconds per move for fixed
+    ///  * 'increment' - The increment in seconds, only used for increment
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let control = TimeControl::parse("increment", "5", "3");
+    /// ```
+    pub fn parse(kind: &str, minutes: &str, increment: &str) -> Option<TimeControl> {
+        match kind.to_lowercase().as_str() {
+            "" | "none" => Some(TimeControl::Unlimited),
+            "sudden" => {
+                let total = minutes.trim().parse::<f64>().ok()?;
+                Some(TimeControl::SuddenDeath { total: Duration::from_secs_f64(total * 60.0) })
+            }
+            "increment" => {
+                let total = minutes.trim().parse::<f64>().ok()?;
+                let increment = increment.trim().parse::<f64>().ok()?;
+                Some(TimeControl::Increment {
+                    total: Duration::from_secs_f64(total * 60.0),
+                    increment: Duration::from_secs_f64(increment),
+                })
+            }
+            "fixed" => {
+                let per_move = minutes.trim().parse::<f64>().ok()?;
+                Some(TimeControl::FixedPerMove { per_move: Duration::from_secs_f64(per_move) })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The clock of a single player
+pub struct Clock {
+    control: TimeControl,
+    remaining: Duration,
+    turn_start: Option<Instant>,
+}
+
+impl Clock {
+    /// Creates a clock with the full starting time of the given time control
+    pub fn new(control: TimeControl) -> Self {
+        let remaining = match control {
+            TimeControl::Unlimited => Duration::MAX,
+            TimeControl::SuddenDeath { total } => total,
+            TimeControl::Increment { total, .. } => total,
+            TimeControl::FixedPerMove { per_move } => per_move,
+        };
+        Self { control, remaining, turn_start: None }
+    }
+
+    /// Starts the clock for the current turn. Does nothing if it is already running,
+    /// so an invalid move does not give the player their time back.
+    pub fn start_turn(&mut self) {
+        if self.turn_start.is_none() {
+            self.turn_start = Some(Instant::now());
+        }
+    }
+
+    ///Stops the clock after a move was played and charges the time used.
+    /// Returns false if the player ran out of time during the turn
+    pub fn stop_turn(&mut self) -> bool {
+        let elapsed = match self.turn_start.take() {
+            Some(start) => start.elapsed(),
+            None => Duration::ZERO,
+        };
+        match self.control {
+            TimeControl::Unlimited => true,
+            TimeControl::SuddenDeath { .. } => {
+                let in_time = elapsed <= self.remaining;
+                self.remaining = self.remaining.saturating_sub(elapsed);
+                in_time
+            }
+            TimeControl::Increment { increment, .. } => {
+                let in_time = elapsed <= self.remaining;
+                self.remaining = self.remaining.saturating_sub(elapsed);
+                if in_time {
+                    self.remaining += increment;
+                }
+                in_time
+            }
+            TimeControl::FixedPerMove { per_move } => {
+                // Unused time does not carry over to the next move
+                self.remaining = per_move;
+                elapsed <= per_move
+            }
+        }
+    }
+
+    /// Returns true if the time of the running turn has already been used up
+    pub fn is_flagged(&self) -> bool {
+        match (self.control, self.turn_start) {
+            (TimeControl::Unlimited, _) | (_, None) => false,
+            (_, Some(start)) => start.elapsed() > self.remaining,
+        }
+    }
+
+    /// Returns the time left on the clock, including the running turn
+    pub fn remaining(&self) -> Duration {
+        match self.turn_start {
+            Some(start) => self.remaining.saturating_sub(start.elapsed()),
+            None => self.remaining,
+        }
+    }
+
+    /// Returns true if the clock is counting down
+    pub fn is_timed(&self) -> bool {
+        !matches!(self.control, TimeControl::Unlimited)
+    }
+
+    ///Returns how long the AI should think for its next move.
+    /// The remaining time is spread over the moves this player is still expected to make,
+    /// which is estimated as half of the empty squares left on the board.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'empty_squares' - The number of empty squares on the board
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let budget = clock.allocate(60);
+    /// let ai_move = ai_handler.get_ai_move(&possible_moves, board.clone(), budget);
+    /// ```
+    pub fn allocate(&self, empty_squares: u32) -> Duration {
+        let remaining = self.remaining();
+        let moves_left = empty_squares.div_ceil(2).max(1);
+        let budget = match self.control {
+            TimeControl::Unlimited => return constants::DEFAULT_AI_MOVE_TIME,
+            TimeControl::SuddenDeath { .. } => remaining / moves_left,
+            // The increment arrives after every move, so most of it can be spent right away
+            TimeControl::Increment { increment, .. } => remaining / moves_left + increment * 3 / 4,
+            TimeControl::FixedPerMove { .. } => remaining,
+        };
+        budget
+            .min(remaining)
+            .saturating_sub(constants::CLOCK_SAFETY_MARGIN)
+    }
+}
+
+///Formats a duration as minutes and seconds for printing next to the board
+///
+///  # Arguments
+///
+///  * 'duration' - The time to format
+pub fn format_time(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}.{}", seconds / 60, seconds % 60, duration.subsec_millis() / 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn parses_every_time_control() {
+        assert!(matches!(TimeControl::parse("none", "", ""), Some(TimeControl::Unlimited)));
+        assert!(matches!(TimeControl::parse("sudden", "5", ""), Some(TimeControl::SuddenDeath { total }) if total == minutes(5)));
+        assert!(matches!(TimeControl::parse("increment", "3", "2"),
+            Some(TimeControl::Increment { total, increment }) if total == minutes(3) && increment == Duration::from_secs(2)));
+        assert!(matches!(TimeControl::parse("fixed", "10", ""), Some(TimeControl::FixedPerMove { per_move }) if per_move == Duration::from_secs(10)));
+        assert!(TimeControl::parse("sudden", "five", "").is_none());
+        assert!(TimeControl::parse("bullet", "1", "").is_none());
+    }
+
+    #[test]
+    fn allocate_spreads_the_bank_over_the_moves_left() {
+        let unlimited = Clock::new(TimeControl::Unlimited);
+        assert_eq!(unlimited.allocate(60), constants::DEFAULT_AI_MOVE_TIME);
+
+        // 60 empty squares leave this player about 30 moves
+        let sudden = Clock::new(TimeControl::SuddenDeath { total: minutes(5) });
+        assert_eq!(sudden.allocate(60), Duration::from_secs(10) - constants::CLOCK_SAFETY_MARGIN);
+        // An odd count rounds up, and the last move may use the whole bank
+        assert_eq!(sudden.allocate(59), Duration::from_secs(10) - constants::CLOCK_SAFETY_MARGIN);
+        assert_eq!(sudden.allocate(0), minutes(5) - constants::CLOCK_SAFETY_MARGIN);
+        assert!(sudden.allocate(10) > sudden.allocate(40));
+
+        let increment = Clock::new(TimeControl::Increment { total: minutes(5), increment: Duration::from_secs(4) });
+        assert_eq!(increment.allocate(60), Duration::from_secs(13) - constants::CLOCK_SAFETY_MARGIN);
+
+        let fixed = Clock::new(TimeControl::FixedPerMove { per_move: Duration::from_secs(2) });
+        assert_eq!(fixed.allocate(60), Duration::from_secs(2) - constants::CLOCK_SAFETY_MARGIN);
+        assert_eq!(fixed.allocate(2), Duration::from_secs(2) - constants::CLOCK_SAFETY_MARGIN);
+    }
+
+    #[test]
+    fn allocate_never_exceeds_the_time_left() {
+        let clock = Clock::new(TimeControl::Increment { total: Duration::from_secs(1), increment: Duration::from_secs(10) });
+        assert_eq!(clock.allocate(2), Duration::from_secs(1) - constants::CLOCK_SAFETY_MARGIN);
+        let nearly_flagged = Clock::new(TimeControl::SuddenDeath { total: Duration::from_millis(10) });
+        assert_eq!(nearly_flagged.allocate(20), Duration::ZERO);
+    }
+
+    #[test]
+    fn increment_is_added_after_every_move_in_time() {
+        let mut clock = Clock::new(TimeControl::Increment { total: minutes(1), increment: Duration::from_secs(5) });
+        clock.start_turn();
+        assert!(clock.stop_turn());
+        assert!(clock.remaining() > minutes(1));
+        assert!(clock.remaining() <= minutes(1) + Duration::from_secs(5));
+    }
+
+    #[test]
+    fn fixed_per_move_time_does_not_carry_over() {
+        let per_move = Duration::from_secs(3);
+        let mut clock = Clock::new(TimeControl::FixedPerMove { per_move });
+        clock.start_turn();
+        assert!(clock.stop_turn());
+        assert_eq!(clock.remaining(), per_move);
+        clock.start_turn();
+        assert!(clock.stop_turn());
+        assert_eq!(clock.remaining(), per_move);
+    }
+
+    #[test]
+    fn flags_once_the_time_reaches_zero() {
+        let mut clock = Clock::new(TimeControl::SuddenDeath { total: Duration::ZERO });
+        assert!(!clock.is_flagged());
+        clock.start_turn();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(clock.is_flagged());
+        assert_eq!(clock.remaining(), Duration::ZERO);
+        assert!(!clock.stop_turn());
+        assert_eq!(clock.remaining(), Duration::ZERO);
+
+        let mut unlimited = Clock::new(TimeControl::Unlimited);
+        unlimited.start_turn();
+        assert!(!unlimited.is_flagged());
+        assert!(unlimited.stop_turn());
+    }
+
+    #[test]
+    fn formats_minutes_seconds_and_tenths() {
+        assert_eq!(format_time(Duration::from_millis(83_450)), "1:23.4");
+        assert_eq!(format_time(Duration::ZERO), "0:00.0");
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::time::Duration;
+
 // -----constants----- //
 pub const BOARD_SIZE: usize = 8;
 pub const STARTING_BLACK: u64 = 0x0000000810000000;
@@ -5,21 +8,139 @@ pub const STARTING_BLACK: u64 = 0x0000000810000000;
 pub const STARTING_WHITE: u64 = 0x0000001008000000;
 pub const STARTING_PLAYER: Color = Color::Black;
 //Black goes first
-pub const EDGE_MASK: u64 = 0xff818181818181ff;
 pub const FULL: u64 = 0xffffffffffffffff;
-//using mod operator to get remainder. This is to get around shifting by negative values with a usize
-pub const DIRECTIONS: [usize; 8] = [19, 18, 17, 11, 1, 7, 8, 9];
-#[derive(Clone, Copy)]
+//columns a and h, used to stop shifts from wrapping around to the other side of the board
+pub const FILE_A: u64 = 0x0101010101010101;
+pub const FILE_H: u64 = 0x8080808080808080;
+//smallest board that can be played, and the board sizes offered at the start of a game
+pub const MIN_BOARD_SIZE: usize = 4;
+pub const BOARD_SIZES: [usize; 3] = [4, 6, 8];
+//think time used by the AI when the game is played without a clock
+pub const DEFAULT_AI_MOVE_TIME: Duration = Duration::from_secs(30);
+//think time used by the engine when the player asks for a hint
+pub const HINT_TIME: Duration = Duration::from_secs(5);
+//number of moves of the principal variation shown with a hint
+pub const HINT_PV_LENGTH: usize = 8;
+//think time per position of the post-game review
+pub const REVIEW_TIME_PER_POSITION: Duration = Duration::from_secs(1);
+//time held back from every allocation so the engine never flags on overhead
+pub const CLOCK_SAFETY_MARGIN: Duration = Duration::from_millis(50);
+//Multi-ProbCut selectivity level searches use unless --selectivity is given
+pub const DEFAULT_SELECTIVITY: usize = 2;
+//file the calibrate command writes the Multi-ProbCut regressions to
+pub const PROBCUT_FILE: &str = "probcut.txt";
+//weights file the neural evaluator reads unless --network is given
+pub const NEURAL_NETWORK_FILE: &str = "network.bin";
+//directory the train command keeps its samples and every accepted network in
+pub const TRAINING_DIRECTORY: &str = "training";
+//file the adaptive mode keeps the player's level and record in
+pub const PROFILE_FILE: &str = "profile.txt";
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Color{
     Black,
     White
 }
 
+impl Color {
+    /// Returns the color of the other player
+    pub fn opponent(&self) -> Color {
+        match self {
+            Color::Black => Color::White,
+            Color::White => Color::Black,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::Black => write!(f, "Black"),
+            Color::White => write!(f, "White"),
+        }
+    }
+}
+
+/// The rules a game is played by
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    // The player with the most discs wins
+    Standard,
+    // Anti-Othello, the player with the fewest discs wins
+    Anti
+}
+
+impl Variant {
+    /// Returns the variant with the given name, as printed by Display
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "standard" => Some(Variant::Standard),
+            "anti" | "misere" => Some(Variant::Anti),
+            _ => None,
+        }
+    }
+
+    /// Returns 1 if discs are worth having and -1 if they are not
+    pub fn disc_sign(self) -> i32 {
+        match self {
+            Variant::Standard => 1,
+            Variant::Anti => -1,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "standard"),
+            Variant::Anti => write!(f, "anti"),
+        }
+    }
+}
+
+///Returns where the tables the engines keep for the 4x4, 6x6 and 8x8 boards hold a board size
+pub const fn size_index(size: usize) -> usize {
+    (size - MIN_BOARD_SIZE) / 2
+}
+
+///Returns how far a square is from the nearest edge of a board of the given size, across the rows
+/// and along them with the smaller distance first, or None if the square is off the board. A corner
+/// is (0, 0) and an X-square (1, 1) on every size, so the square tables of every size are built from it
+///
+///  # Arguments
+///
+///  * 'index' - The square, a1 being 0 and rows BOARD_SIZE squares apart
+///  * 'size' - The number of rows and columns of the board
+///
+///  # Examples
+///
+/// ```
+/// let is_corner = matches!(constants::edge_distances(index, board.size()), Some((0, 0)));
+/// ```
+pub const fn edge_distances(index: usize, size: usize) -> Option<(usize, usize)> {
+    let (row, column) = (index / BOARD_SIZE, index % BOARD_SIZE);
+    if row >= size || column >= size {
+        return None;
+    }
+    let row = if row < size - 1 - row { row } else { size - 1 - row };
+    let column = if column < size - 1 - column { column } else { size - 1 - column };
+    match row < column {
+        true => Some((row, column)),
+        false => Some((column, row)),
+    }
+}
+
 pub enum Difficulty{
+    #[allow(dead_code)]
     None,
     Easy,
     Normal,
-    Hard
+    Hard,
+    // A graded strength level from 1 to ai_logic::strength::LEVELS.len()
+    Level(usize),
+    // A level that follows the player, starting from the one in the player profile
+    Adaptive(usize),
+    // Any engine of the registry, picked by name
+    Engine(crate::ai_logic::AiType)
 }
 
 // ***** Board Layout ***** \\
@@ -32,4 +153,4 @@ e1 e2 e3 e4 e5 e6 e7 e8
 f1 f2 f3 f4 f5 f6 f7 f8
 g1 g2 g3 g4 g5 g6 g7 g8
 h1 h2 h3 h4 h5 h6 h7 h8
-*/
\ No newline at end of file
+*/
@@ -0,0 +1,168 @@
+//Imports
+use std::sync::OnceLock;
+
+//Modules
+use crate::constants;
+
+// Multiplying a masked row or diagonal by this gathers one bit per file into the top byte
+const GATHER_FILES: u64 = constants::FILE_A;
+// Multiplying the a-file by this gathers one bit per rank into the top byte
+const GATHER_RANKS: u64 = 0x8040201008040201;
+// 8 rows, 8 columns, 15 diagonals and 15 anti-diagonals
+const LINE_COUNT: usize = 46;
+
+/// One of the 4 lines through a square
+#[derive(Clone, Copy)]
+struct SquareLine {
+    // Which line it is, used to index the deposit table
+    line: usize,
+    // Where the square sits on the line
+    position: usize,
+}
+
+/// A row, column or diagonal of the board
+#[derive(Clone, Copy)]
+struct Line {
+    mask: u64,
+    // Columns are gathered by rank instead of by file
+    column: Option<u32>,
+}
+
+impl Line {
+    // Packs the squares of the line into the low 8 bits
+    fn extract(&self, bits: u64) -> usize {
+        match self.column {
+            Some(file) => ((((bits >> file) & constants::FILE_A).wrapping_mul(GATHER_RANKS)) >> 56) as usize,
+            None => ((bits & self.mask).wrapping_mul(GATHER_FILES) >> 56) as usize,
+        }
+    }
+}
+
+struct FlipTables {
+    lines: Vec<Line>,
+    square_lines: [[SquareLine; 4]; 64],
+    // Flipped line pattern, indexed by position, player pattern and opponent pattern
+    flips: Vec<u8>,
+    // Bitboard of a line pattern, indexed by line and pattern
+    deposit: Vec<[u64; 256]>,
+}
+
+static TABLES: OnceLock<FlipTables> = OnceLock::new();
+
+///Returns the pieces flipped when player plays on the given square, by looking up
+/// the occupancy of the row, column and both diagonals through the square.
+/// The tables are built the first time this is called.
+///
+///  # Arguments
+///
+///  * 'player' - The bitboard of the player to move
+///  * 'opponent' - The bitboard of the other player
+///  * 'selected_move' - The square being played as a bitboard
+///
+///  # Examples
+///
+/// ```
+/// let flips = flip_tables::compute_flips(player, opponent, selected_move);
+/// ```
+pub fn compute_flips(player: u64, opponent: u64, selected_move: u64) -> u64 {
+    let tables = TABLES.get_or_init(build_tables);
+    let square = selected_move.trailing_zeros() as usize;
+    let mut flip_mask: u64 = 0;
+    for square_line in tables.square_lines[square] {
+        let line = tables.lines[square_line.line];
+        let index = (square_line.position << 16) | (line.extract(player) << 8) | line.extract(opponent);
+        flip_mask |= tables.deposit[square_line.line][tables.flips[index] as usize];
+    }
+    flip_mask
+}
+
+/// Builds the tables ahead of time so the first move does not pay for it
+pub fn initialize() {
+    TABLES.get_or_init(build_tables);
+}
+
+fn build_tables() -> FlipTables {
+    let mut lines: Vec<Line> = Vec::with_capacity(LINE_COUNT);
+    let mut square_lines = [[SquareLine { line: 0, position: 0 }; 4]; 64];
+
+    // Group the squares into lines, a square is on line (kind, key) for each of the 4 kinds
+    let line_key = |square: usize, kind: usize| -> (usize, i32) {
+        let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+        match kind {
+            0 => (kind, rank),
+            1 => (kind, file),
+            2 => (kind, rank - file),
+            _ => (kind, rank + file),
+        }
+    };
+    let mut keys: Vec<(usize, i32)> = Vec::with_capacity(LINE_COUNT);
+    for kind in 0..4 {
+        for square in 0..64 {
+            let key = line_key(square, kind);
+            if !keys.contains(&key) {
+                let mask = (0..64).filter(|&other| line_key(other, kind) == key).fold(0u64, |mask, other| mask | (1 << other));
+                let column = match kind {
+                    1 => Some((square % 8) as u32),
+                    _ => None,
+                };
+                keys.push(key);
+                lines.push(Line { mask, column });
+            }
+        }
+    }
+
+    // Deposit tables, built by extracting each square on its own so they always agree with extract
+    let mut deposit: Vec<[u64; 256]> = vec![[0; 256]; lines.len()];
+    for (line_index, line) in lines.iter().enumerate() {
+        for square in 0..64 {
+            if line.mask & (1 << square) == 0 {
+                continue;
+            }
+            let bit = line.extract(1 << square);
+            for (pattern, bitboard) in deposit[line_index].iter_mut().enumerate() {
+                if pattern & bit != 0 {
+                    *bitboard |= 1 << square;
+                }
+            }
+        }
+    }
+    for (square, square_line) in square_lines.iter_mut().enumerate() {
+        for (kind, entry) in square_line.iter_mut().enumerate() {
+            let line = keys.iter().position(|&key| key == line_key(square, kind)).unwrap_or(0);
+            let position = lines[line].extract(1 << square).trailing_zeros() as usize;
+            *entry = SquareLine { line, position };
+        }
+    }
+
+    // Flip patterns for every position on a line of 8
+    let mut flips: Vec<u8> = vec![0; 8 * 256 * 256];
+    for position in 0..8 {
+        for player in 0..256usize {
+            for opponent in 0..256usize {
+                if player & opponent != 0 || (player | opponent) & (1 << position) != 0 {
+                    continue;
+                }
+                flips[(position << 16) | (player << 8) | opponent] = line_flips(player, opponent, position);
+            }
+        }
+    }
+
+    FlipTables { lines, square_lines, flips, deposit }
+}
+
+// Walks both ways from position and returns the opponent runs closed off by a player piece
+fn line_flips(player: usize, opponent: usize, position: usize) -> u8 {
+    let mut flipped: usize = 0;
+    for step in [-1i32, 1] {
+        let mut run: usize = 0;
+        let mut index = position as i32 + step;
+        while (0..8).contains(&index) && opponent & (1 << index) != 0 {
+            run |= 1 << index;
+            index += step;
+        }
+        if (0..8).contains(&index) && player & (1 << index) != 0 {
+            flipped |= run;
+        }
+    }
+    flipped as u8
+}
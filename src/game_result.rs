@@ -0,0 +1,187 @@
+//Imports
+use std::fmt;
+
+//Modules
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::constants;
+
+/// Why the game came to an end
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EndReason {
+    BoardFull,
+    BothPassed,
+    WipeOut,
+    Timeout,
+    Resignation
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EndReason::BoardFull => write!(f, "the board is full"),
+            EndReason::BothPassed => write!(f, "neither player can move"),
+            EndReason::WipeOut => write!(f, "a player has no discs left"),
+            EndReason::Timeout => write!(f, "a player ran out of time"),
+            EndReason::Resignation => write!(f, "a player resigned"),
+        }
+    }
+}
+
+/// The final result of a game, scored with empty squares going to the winner
+#[derive(Clone, Copy, Debug)]
+pub struct GameResult {
+    pub winner: Option<constants::Color>,
+    pub black_discs: u32,
+    pub white_discs: u32,
+    pub reason: EndReason
+}
+
+impl GameResult {
+    ///Scores a finished board. The winner is the player with the most discs, or the fewest in
+    /// anti-Othello. The empty squares are added to the winner's discs, or to the loser's in
+    /// anti-Othello, so they always widen the margin. On a draw the empty squares are split evenly.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The final position
+    ///  * 'reason' - Why the game ended
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// if let Some(reason) = game_board.game_over_reason() {
+    ///     let result = GameResult::from_board(&game_board, reason);
+    /// }
+    /// ```
+    pub fn from_board<B: Bitboard>(board: &Board<B>, reason: EndReason) -> Self {
+        let winner = match board.disc_margin(constants::Color::Black).cmp(&0) {
+            std::cmp::Ordering::Greater => Some(constants::Color::Black),
+            std::cmp::Ordering::Less => Some(constants::Color::White),
+            std::cmp::Ordering::Equal => None,
+        };
+        Self::with_winner(board, winner, reason)
+    }
+
+    ///Scores a game lost on time. The opponent of the flagged player wins and the empty squares are
+    /// counted as for any other win
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position when the flag fell
+    ///  * 'flagged' - The player who ran out of time
+    pub fn timeout<B: Bitboard>(board: &Board<B>, flagged: constants::Color) -> Self {
+        Self::with_winner(board, Some(flagged.opponent()), EndReason::Timeout)
+    }
+
+    ///Scores a resigned game. The opponent of the resigning player wins and the empty squares are
+    /// counted as for any other win
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'board' - The position the player resigned in
+    ///  * 'resigned' - The player who gave up
+    pub fn resignation<B: Bitboard>(board: &Board<B>, resigned: constants::Color) -> Self {
+        Self::with_winner(board, Some(resigned.opponent()), EndReason::Resignation)
+    }
+
+    fn with_winner<B: Bitboard>(board: &Board<B>, winner: Option<constants::Color>, reason: EndReason) -> Self {
+        let mut black_discs = board.count_discs(constants::Color::Black);
+        let mut white_discs = board.count_discs(constants::Color::White);
+        let empty_squares = board.count_empty();
+        let receiver = match board.variant {
+            constants::Variant::Standard => winner,
+            constants::Variant::Anti => winner.map(|color| color.opponent()),
+        };
+        match receiver {
+            Some(constants::Color::Black) => black_discs += empty_squares,
+            Some(constants::Color::White) => white_discs += empty_squares,
+            None => {
+                black_discs += empty_squares / 2;
+                white_discs += empty_squares / 2;
+            }
+        }
+        Self { winner, black_discs, white_discs, reason }
+    }
+
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.winner {
+            Some(constants::Color::Black) => write!(f, "Black wins {} to {}", self.black_discs, self.white_discs)?,
+            Some(constants::Color::White) => write!(f, "White wins {} to {}", self.white_discs, self.black_discs)?,
+            None => write!(f, "The game is a draw, {} to {}", self.black_discs, self.white_discs)?,
+        }
+        write!(f, " because {}.", self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Black 3 and white 11 with 2 empty squares on a 4x4 board
+    fn small_finished_board() -> Board {
+        let mut board: Board = Board::with_size(4).unwrap();
+        board.black = 0x0007;
+        board.white = 0x030f_0f08;
+        board
+    }
+
+    #[test]
+    fn disc_count_decides_the_game_and_empties_go_to_the_winner() {
+        let result = GameResult::from_board(&small_finished_board(), EndReason::BothPassed);
+        assert_eq!((result.winner, result.black_discs, result.white_discs), (Some(constants::Color::White), 3, 13));
+        assert_eq!(result.to_string(), "White wins 13 to 3 because neither player can move.");
+
+        // A full board is scored as it stands
+        let mut full: Board = Board::new();
+        full.black = 0xffff_ffff_0000_0000;
+        full.white = 0x0000_0000_ffff_ffff;
+        assert_eq!(full.game_over_reason(), Some(EndReason::BoardFull));
+        let draw = GameResult::from_board(&full, EndReason::BoardFull);
+        assert_eq!((draw.winner, draw.black_discs, draw.white_discs), (None, 32, 32));
+
+        // A drawn game with empty squares splits them evenly
+        let mut split: Board = Board::with_size(4).unwrap();
+        split.black = 0x0007;
+        split.white = 0x0700;
+        let draw = GameResult::from_board(&split, EndReason::BothPassed);
+        assert_eq!((draw.winner, draw.black_discs, draw.white_discs), (None, 8, 8));
+
+        let mut wiped_out: Board = Board::new();
+        wiped_out.white = 0;
+        assert_eq!(wiped_out.game_over_reason(), Some(EndReason::WipeOut));
+        let result = GameResult::from_board(&wiped_out, EndReason::WipeOut);
+        assert_eq!((result.winner, result.black_discs, result.white_discs), (Some(constants::Color::Black), 64, 0));
+    }
+
+    #[test]
+    fn time_out_loses_whatever_the_discs_say() {
+        // White is ahead on discs but flags
+        let result = GameResult::timeout(&small_finished_board(), constants::Color::White);
+        assert_eq!((result.winner, result.black_discs, result.white_discs), (Some(constants::Color::Black), 5, 11));
+        assert_eq!(result.reason, EndReason::Timeout);
+        assert_eq!(result.to_string(), "Black wins 5 to 11 because a player ran out of time.");
+    }
+
+    #[test]
+    fn resignation_loses_whatever_the_discs_say() {
+        let result = GameResult::resignation(&small_finished_board(), constants::Color::White);
+        assert_eq!((result.winner, result.black_discs, result.white_discs), (Some(constants::Color::Black), 5, 11));
+        assert_eq!(result.reason, EndReason::Resignation);
+        let result = GameResult::resignation(&Board::new(), constants::Color::Black);
+        assert_eq!((result.winner, result.black_discs, result.white_discs), (Some(constants::Color::White), 2, 62));
+        assert_eq!(result.to_string(), "White wins 62 to 2 because a player resigned.");
+    }
+
+    #[test]
+    fn anti_othello_is_won_with_fewer_discs() {
+        let board = small_finished_board();
+        let standard = GameResult::from_board(&board, EndReason::BothPassed);
+        assert_eq!((standard.winner, standard.black_discs, standard.white_discs), (Some(constants::Color::White), 3, 13));
+        let anti = GameResult::from_board(&board.with_variant(constants::Variant::Anti), EndReason::BothPassed);
+        assert_eq!((anti.winner, anti.black_discs, anti.white_discs), (Some(constants::Color::Black), 3, 13));
+    }
+}
@@ -1,9 +1,186 @@
-use crate::board::Board;
-
 mod ai_logic;
 mod constants;
 mod board;
+mod clock;
+mod review;
+mod game_result;
+mod bench;
+mod flip_tables;
+mod stability;
+mod tournament;
+mod profile;
+mod seed;
+mod bitboard;
+
+use crate::ai_logic::{mtdf, neural_network, probcut, training, AiType, EngineOptions, Evaluator, SearchAlgorithm};
+use crate::ai_logic::personality::{self, Personality};
+use crate::ai_logic::monte_carlo_tree_search::{LeafEvaluation, MctsSettings, PriorSource, RaveSchedule};
+use crate::board::{Board, FlipMethod};
+use std::time::Duration;
+
+// perft depth used by the bench command when none is given
+const DEFAULT_BENCH_DEPTH: usize = 9;
+// self-play games and deepest search used by the calibrate command when none are given
+const DEFAULT_CALIBRATION_GAMES: usize = 20;
+const DEFAULT_CALIBRATION_DEPTH: i32 = 8;
+// search depth used by the compare command when none is given
+const DEFAULT_COMPARISON_DEPTH: i32 = 8;
+// training iterations and self-play games per iteration used by the train command when none are given
+const DEFAULT_TRAINING_ITERATIONS: usize = 10;
+const DEFAULT_TRAINING_GAMES: usize = 100;
+// games and playouts per move used by the RAVE benchmark when none are given
+const DEFAULT_BENCHMARK_GAMES: usize = 100;
+const DEFAULT_BENCHMARK_PLAYOUTS: usize = 1000;
+// games per match and think time per move used by the strength level ladder when none are given
+const DEFAULT_LADDER_GAMES: usize = 40;
+const DEFAULT_LADDER_MOVE_TIME_MS: u64 = 200;
 
 fn main() {
-    board::run_game();
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--flip-tables") {
+        Board::set_flip_method(FlipMethod::Table);
+    }
+    let mut options = EngineOptions::default();
+    let seed = match args.iter().position(|arg| arg == "--seed").and_then(|index| args.get(index + 1)) {
+        Some(seed) => seed.parse::<u64>().unwrap_or_else(|_| {
+            println!("The seed must be a whole number from 0 to {}, using a random one", u64::MAX);
+            seed::random_seed()
+        }),
+        None => seed::random_seed(),
+    };
+    if args.iter().any(|arg| arg == "--mtdf") {
+        options.algorithm = SearchAlgorithm::Mtdf;
+    }
+    if args.iter().any(|arg| arg == "--neural") {
+        let path = args.iter().position(|arg| arg == "--network").and_then(|index| args.get(index + 1))
+            .map_or(constants::NEURAL_NETWORK_FILE, |path| path.as_str());
+        match neural_network::load(path) {
+            Ok(_) => {
+                options.evaluator = Evaluator::Neural;
+                options.mcts = MctsSettings::network();
+            }
+            Err(error) => println!("Using the heuristic evaluator, the network could not be loaded: {}", error),
+        }
+    }
+    if let Some(name) = args.iter().position(|arg| arg == "--personality").and_then(|index| args.get(index + 1)) {
+        match Personality::from_name(name) {
+            Some(personality) => {
+                options.personality = personality;
+                options.selectivity = personality.selectivity();
+            }
+            None => {
+                let names: Vec<String> = personality::PERSONALITIES.iter().map(|personality| personality.to_string()).collect();
+                println!("The personality must be one of {}", names.join(", "));
+            }
+        }
+    }
+    // Games ask for the rules unless they were given here
+    let variant = args.iter().position(|arg| arg == "--variant").and_then(|index| args.get(index + 1)).map(|name| {
+        constants::Variant::from_name(name).unwrap_or_else(|| {
+            println!("The variant must be standard or anti, playing standard Othello");
+            constants::Variant::Standard
+        })
+    });
+    if let Some(level) = args.iter().position(|arg| arg == "--selectivity").and_then(|index| args.get(index + 1)) {
+        match level.parse::<usize>() {
+            Ok(level) => options.selectivity = level.min(probcut::max_selectivity()),
+            Err(_) => println!("Selectivity must be a level from 0 to {}", probcut::max_selectivity()),
+        }
+    }
+    if let Some(exploration) = args.iter().position(|arg| arg == "--exploration").and_then(|index| args.get(index + 1)) {
+        match exploration.parse::<f32>() {
+            Ok(exploration) if exploration >= 0.0 => options.mcts.exploration = exploration,
+            _ => println!("The exploration constant must be a number of at least 0"),
+        }
+    }
+    if let Some(priors) = args.iter().position(|arg| arg == "--mcts-priors").and_then(|index| args.get(index + 1)) {
+        match priors.as_str() {
+            "uniform" => options.mcts.priors = PriorSource::Uniform,
+            "ordering" => options.mcts.priors = PriorSource::MoveOrdering,
+            "network" => options.mcts.priors = PriorSource::Network,
+            _ => println!("Tree search priors must be uniform, ordering or network"),
+        }
+    }
+    if let Some(leaf) = args.iter().position(|arg| arg == "--mcts-leaf").and_then(|index| args.get(index + 1)) {
+        match leaf.as_str() {
+            "rollout" => options.mcts.leaf = LeafEvaluation::Rollout,
+            "evaluate" => options.mcts.leaf = LeafEvaluation::Evaluate,
+            "network" => options.mcts.leaf = LeafEvaluation::Network,
+            _ => println!("Tree search leaves must be scored by rollout, evaluate or network"),
+        }
+    }
+    if let Some(cutoff) = args.iter().position(|arg| arg == "--rollout-cutoff").and_then(|index| args.get(index + 1)) {
+        match cutoff.parse::<usize>() {
+            Ok(cutoff) => options.mcts.rollout_cutoff = cutoff,
+            Err(_) => println!("The rollout cutoff must be a number of moves"),
+        }
+    }
+    if let Some(empties) = args.iter().position(|arg| arg == "--mcts-solver").and_then(|index| args.get(index + 1)) {
+        match empties.parse::<u32>() {
+            Ok(empties) => options.mcts.solver_empties = empties,
+            Err(_) => println!("The tree search solver limit must be a number of empty squares"),
+        }
+    }
+    if let Some(k) = args.iter().position(|arg| arg == "--rave").and_then(|index| args.get(index + 1)) {
+        match k.parse::<f32>() {
+            Ok(k) if k > 0.0 => options.mcts.rave = Some(RaveSchedule::Equivalence(k)),
+            _ => println!("The RAVE equivalence constant must be a number above 0"),
+        }
+    }
+    if let Some(bias) = args.iter().position(|arg| arg == "--rave-bias").and_then(|index| args.get(index + 1)) {
+        match bias.parse::<f32>() {
+            Ok(bias) if bias >= 0.0 => options.mcts.rave = Some(RaveSchedule::MinimumError(bias)),
+            _ => println!("The RAVE bias must be a number of at least 0"),
+        }
+    }
+    if let Some(nodes) = args.iter().position(|arg| arg == "--mcts-nodes").and_then(|index| args.get(index + 1)) {
+        match nodes.parse::<usize>() {
+            Ok(nodes) => options.mcts.max_nodes = nodes,
+            Err(_) => println!("The tree search node budget must be a number of nodes"),
+        }
+    }
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("bench") => {
+            let depth = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_BENCH_DEPTH);
+            bench::run_bench(depth);
+        }
+        Some("calibrate") => {
+            let games = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_CALIBRATION_GAMES);
+            let depth = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_CALIBRATION_DEPTH);
+            probcut::calibrate(games, depth, seed);
+        }
+        Some("compare") => {
+            let depth = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_COMPARISON_DEPTH);
+            mtdf::run_comparison(depth);
+        }
+        Some("train") => {
+            let iterations = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_TRAINING_ITERATIONS);
+            let games = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_TRAINING_GAMES);
+            training::run_training(iterations, games, seed);
+        }
+        Some("tournament") => match args.get(2).map(|arg| arg.as_str()) {
+            Some("rave") => {
+                let games = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_BENCHMARK_GAMES);
+                let playouts = args.get(4).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_BENCHMARK_PLAYOUTS);
+                tournament::run_rave_benchmark(options.mcts, games, playouts, seed);
+            }
+            Some("levels") => {
+                let games = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_LADDER_GAMES);
+                let move_time = args.get(4).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_LADDER_MOVE_TIME_MS);
+                tournament::run_level_ladder(options, games, Duration::from_millis(move_time), seed);
+            }
+            Some("match") => match (args.get(3).and_then(|name| AiType::from_name(name)), args.get(4).and_then(|name| AiType::from_name(name))) {
+                (Some(first), Some(second)) => {
+                    let games = args.get(5).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_BENCHMARK_GAMES);
+                    let move_time = args.get(6).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_LADDER_MOVE_TIME_MS);
+                    tournament::run_engine_match(first, second, options, games, Duration::from_millis(move_time), variant.unwrap_or(constants::Variant::Standard), seed);
+                }
+                _ => println!("Engines: {}", ai_logic::ENGINE_NAMES),
+            },
+            _ => println!("Tournaments: rave [games] [playouts], levels [games] [milliseconds per move], match <engine> <engine> [games] [milliseconds per move] [--variant anti]"),
+        },
+        _ => {
+            board::run_game(options, variant, seed);
+        }
+    }
 }
@@ -0,0 +1,214 @@
+//Imports
+use std::fs;
+use std::io;
+
+//Modules
+use crate::ai_logic::strength;
+use crate::constants;
+use crate::game_result::GameResult;
+use crate::review::Annotation;
+
+// Level a player without a profile starts at
+const STARTING_LEVEL: usize = 5;
+// Measured human moves between adjustments of the level during a game
+const ADJUSTMENT_MOVES: usize = 8;
+// Share of accurate moves at or above which the engine gets stronger, and at or below which it gets weaker
+const RAISE_ACCURACY: f64 = 0.75;
+const LOWER_ACCURACY: f64 = 0.45;
+// Weight of the latest game in the running accuracy of the profile
+const ACCURACY_SMOOTHING: f64 = 0.3;
+
+/// What the adaptive mode remembers about the human between sessions
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerProfile {
+    // The level the next adaptive game starts at
+    pub level: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    // Running share of accurate moves over the adaptive games, as a percentage
+    pub accuracy: f64
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        Self { level: STARTING_LEVEL, wins: 0, draws: 0, losses: 0, accuracy: 0.0 }
+    }
+}
+
+impl PlayerProfile {
+    ///Reads the profile from PROFILE_FILE, or starts a new one if there is none or it can not be read
+    pub fn load() -> Self {
+        match fs::read_to_string(constants::PROFILE_FILE) {
+            Ok(text) => PlayerProfile::parse(&text).unwrap_or_else(|| {
+                println!("{} is not a valid player profile, starting a new one", constants::PROFILE_FILE);
+                PlayerProfile::default()
+            }),
+            Err(_) => PlayerProfile::default(),
+        }
+    }
+
+    ///Writes the profile to PROFILE_FILE
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(constants::PROFILE_FILE, self.to_text())
+    }
+
+    ///Returns the number of adaptive games played
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    ///Adds a finished adaptive game to the profile and moves the starting level of the next game.
+    /// The next game starts from the level the game ended at, one higher after a win and one
+    /// lower after a loss. Returns the new starting level.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'human' - The color the human played
+    ///  * 'result' - The result of the game
+    ///  * 'level' - The level the engine played at when the game ended
+    ///  * 'accuracy' - The human's share of accurate moves in the game, as a percentage
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let next_level = profile.record_game(player, &result, adaptive.level(), adaptive.accuracy());
+    /// ```
+    pub fn record_game(&mut self, human: constants::Color, result: &GameResult, level: usize, accuracy: f64) -> usize {
+        let level = match result.winner {
+            Some(winner) if winner == human => {
+                self.wins += 1;
+                level + 1
+            }
+            Some(_) => {
+                self.losses += 1;
+                level.saturating_sub(1)
+            }
+            None => {
+                self.draws += 1;
+                level
+            }
+        };
+        self.accuracy = match self.games() {
+            1 => accuracy,
+            _ => (1.0 - ACCURACY_SMOOTHING) * self.accuracy + ACCURACY_SMOOTHING * accuracy,
+        };
+        self.level = level.clamp(1, strength::LEVELS.len());
+        self.level
+    }
+
+    // One "key value" line per field
+    fn parse(text: &str) -> Option<Self> {
+        let mut profile = PlayerProfile::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once(' ')?;
+            let value = value.trim();
+            match key {
+                "level" => profile.level = value.parse().ok().filter(|&level| strength::level(level).is_some())?,
+                "wins" => profile.wins = value.parse().ok()?,
+                "draws" => profile.draws = value.parse().ok()?,
+                "losses" => profile.losses = value.parse().ok()?,
+                "accuracy" => profile.accuracy = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(profile)
+    }
+
+    fn to_text(&self) -> String {
+        format!("# adaptive player profile\nlevel {}\nwins {}\ndraws {}\nlosses {}\naccuracy {:.2}\n", self.level, self.wins, self.draws, self.losses, self.accuracy)
+    }
+}
+
+/// Moves the engine's level during a game, following how accurately the human plays
+pub struct AdaptiveStrength {
+    level: usize,
+    // Accurate and measured moves since the last adjustment
+    accurate: usize,
+    measured: usize,
+    // Accurate and measured moves over the whole game
+    game_accurate: usize,
+    game_measured: usize
+}
+
+impl AdaptiveStrength {
+    ///Starts adapting from the given level
+    pub fn new(level: usize) -> Self {
+        Self { level: level.clamp(1, strength::LEVELS.len()), accurate: 0, measured: 0, game_accurate: 0, game_measured: 0 }
+    }
+
+    ///Returns the level the engine should play at
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    ///Returns the human's share of accurate moves this game, as a percentage
+    pub fn accuracy(&self) -> f64 {
+        match self.game_measured {
+            0 => 100.0,
+            measured => 100.0 * self.game_accurate as f64 / measured as f64,
+        }
+    }
+
+    ///Counts one of the human's moves. Every few moves the level goes up if most of them were
+    /// accurate and down if few were. Returns true when the level changed
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'loss' - How many evaluation points the move lost against the best move
+    pub fn record_move(&mut self, loss: i32) -> bool {
+        let accurate = matches!(Annotation::from_loss(loss), Annotation::Best | Annotation::Good);
+        self.measured += 1;
+        self.game_measured += 1;
+        if accurate {
+            self.accurate += 1;
+            self.game_accurate += 1;
+        }
+        if self.measured < ADJUSTMENT_MOVES {
+            return false;
+        }
+        let share = self.accurate as f64 / self.measured as f64;
+        self.accurate = 0;
+        self.measured = 0;
+        let level = match share {
+            s if s >= RAISE_ACCURACY => (self.level + 1).min(strength::LEVELS.len()),
+            s if s <= LOWER_ACCURACY => (self.level - 1).max(1),
+            _ => self.level,
+        };
+        let changed = level != self.level;
+        self.level = level;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_result::EndReason;
+
+    #[test]
+    fn profile_file_round_trips() {
+        let profile = PlayerProfile { level: 9, wins: 4, draws: 1, losses: 7, accuracy: 62.5 };
+        assert_eq!(PlayerProfile::parse(&profile.to_text()), Some(profile));
+        assert_eq!(PlayerProfile::parse("level 0\n"), None);
+    }
+
+    #[test]
+    fn level_follows_accuracy_and_results() {
+        let mut adaptive = AdaptiveStrength::new(5);
+        let changes: Vec<bool> = (0..ADJUSTMENT_MOVES).map(|_| adaptive.record_move(0)).collect();
+        assert_eq!(changes.iter().filter(|&&changed| changed).count(), 1);
+        assert_eq!(adaptive.level(), 6);
+        for _ in 0..2 * ADJUSTMENT_MOVES {
+            adaptive.record_move(1000);
+        }
+        assert_eq!(adaptive.level(), 4);
+        assert!((adaptive.accuracy() - 100.0 / 3.0).abs() < 1e-9);
+
+        let mut profile = PlayerProfile::default();
+        let result = GameResult { winner: Some(constants::Color::Black), black_discs: 40, white_discs: 24, reason: EndReason::BoardFull };
+        assert_eq!(profile.record_game(constants::Color::Black, &result, adaptive.level(), adaptive.accuracy()), 5);
+        assert_eq!(profile.record_game(constants::Color::White, &result, 1, 50.0), 1);
+        assert_eq!((profile.wins, profile.losses, profile.games()), (1, 1, 2));
+    }
+}
@@ -0,0 +1,262 @@
+//Imports
+use std::fmt;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+//Modules
+use crate::ai_logic::{AiLogicHandler, MoveAnalysis, Verdict};
+use crate::board::Board;
+use crate::constants;
+
+// Score loss, in evaluation points, at which a move gets each label
+const INACCURACY_THRESHOLD: i32 = 40;
+const MISTAKE_THRESHOLD: i32 = 100;
+const BLUNDER_THRESHOLD: i32 = 250;
+// Evaluation points a disc of proven final margin is worth, so solved and searched positions compare
+const PROVEN_DISC_VALUE: i32 = 20;
+// Losses are capped so a single won-to-lost swing does not swamp the average
+const MAX_LOSS: i32 = 1000;
+
+/// The label a move gets from the post-game review
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Annotation {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder
+}
+
+impl Annotation {
+    /// Labels a move by how many evaluation points it lost against the best move
+    pub fn from_loss(loss: i32) -> Self {
+        match loss {
+            l if l <= 0 => Annotation::Best,
+            l if l < INACCURACY_THRESHOLD => Annotation::Good,
+            l if l < MISTAKE_THRESHOLD => Annotation::Inaccuracy,
+            l if l < BLUNDER_THRESHOLD => Annotation::Mistake,
+            _ => Annotation::Blunder,
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Annotation::Best => write!(f, "best"),
+            Annotation::Good => write!(f, "good"),
+            Annotation::Inaccuracy => write!(f, "inaccuracy ?!"),
+            Annotation::Mistake => write!(f, "mistake ?"),
+            Annotation::Blunder => write!(f, "blunder ??"),
+        }
+    }
+}
+
+/// A single played move compared against the engine's best move
+pub struct ReviewedMove {
+    pub player: constants::Color,
+    pub played_move: u64,
+    pub best_move: u64,
+    pub loss: i32,
+    pub annotation: Annotation
+}
+
+/// The annotated moves of a finished game
+pub struct GameReview {
+    pub moves: Vec<ReviewedMove>,
+    // The seed the game was played with, so it can be replayed
+    pub seed: u64
+}
+
+impl GameReview {
+    ///Re-searches every position of a finished game and labels each played move
+    /// by how much worse it was than the engine's best move.
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'ai_handler' - The engine used to search the positions
+    ///  * 'history' - Every position of the game together with the move played in it
+    ///  * 'time_per_position' - How long to search each position
+    ///  * 'seed' - The seed the game was played with, recorded in the transcript
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let review = GameReview::analyze(&mut ai_handler, &history, Duration::from_secs(1), seed);
+    /// print!("{}", review.transcript());
+    /// ```
+    pub fn analyze(ai_handler: &mut AiLogicHandler, history: &[(Board, u64)], time_per_position: Duration, seed: u64) -> Self {
+        let mut moves: Vec<ReviewedMove> = Vec::new();
+        for (number, (position, played_move)) in history.iter().enumerate() {
+            println!("Reviewing move {} of {}", number + 1, history.len());
+            let player = position.current_player;
+            // A forced move can not be a mistake, so it is not searched
+            if position.get_possible_moves().count_ones() <= 1 {
+                moves.push(ReviewedMove { player, played_move: *played_move, best_move: *played_move, loss: 0, annotation: Annotation::Best });
+                continue;
+            }
+
+            let analysis = ai_handler.analyze(position.clone(), time_per_position);
+            let best = &analysis[0];
+            let loss = match analysis.iter().find(|entry| entry.chosen_move == *played_move) {
+                Some(played) => review_score(best).saturating_sub(review_score(played)).clamp(0, MAX_LOSS),
+                None => 0,
+            };
+            moves.push(ReviewedMove { player, played_move: *played_move, best_move: best.chosen_move, loss, annotation: Annotation::from_loss(loss) });
+        }
+        Self { moves, seed }
+    }
+
+    /// Returns the share of the player's moves that lost less than an inaccuracy, as a percentage
+    pub fn accuracy(&self, player: constants::Color) -> f64 {
+        let player_moves: Vec<&ReviewedMove> = self.moves.iter().filter(|reviewed| reviewed.player == player).collect();
+        if player_moves.is_empty() {
+            return 100.0;
+        }
+        let accurate = player_moves.iter().filter(|reviewed| reviewed.loss < INACCURACY_THRESHOLD).count();
+        100.0 * accurate as f64 / player_moves.len() as f64
+    }
+
+    /// Returns the average evaluation points lost per move by the player
+    pub fn average_loss(&self, player: constants::Color) -> f64 {
+        let losses: Vec<i32> = self.moves.iter().filter(|reviewed| reviewed.player == player).map(|reviewed| reviewed.loss).collect();
+        if losses.is_empty() {
+            return 0.0;
+        }
+        losses.iter().sum::<i32>() as f64 / losses.len() as f64
+    }
+
+    /// Returns the number of the player's moves that got the given label
+    pub fn count(&self, player: constants::Color, annotation: Annotation) -> usize {
+        self.moves.iter().filter(|reviewed| reviewed.player == player && reviewed.annotation == annotation).count()
+    }
+
+    /// Returns the seed of the game, the annotated transcript and a summary for each player
+    pub fn transcript(&self) -> String {
+        let mut text = format!("Seed {}\n", self.seed);
+        for (number, reviewed) in self.moves.iter().enumerate() {
+            text += &format!("{:>2}. {}\t{}", number + 1, reviewed.player, Board::convert_to_cords(reviewed.played_move));
+            match reviewed.annotation {
+                Annotation::Best | Annotation::Good => text += "\n",
+                annotation => text += &format!("\t{} (best {}, -{})\n", annotation, Board::convert_to_cords(reviewed.best_move), reviewed.loss),
+            }
+        }
+        for player in [constants::Color::Black, constants::Color::White] {
+            text += &format!(
+                "\n{}: accuracy {:.1}%, average loss {:.1}, {} inaccuracies, {} mistakes, {} blunders",
+                player,
+                self.accuracy(player),
+                self.average_loss(player),
+                self.count(player, Annotation::Inaccuracy),
+                self.count(player, Annotation::Mistake),
+                self.count(player, Annotation::Blunder),
+            );
+        }
+        text += "\n";
+        text
+    }
+
+    ///Writes the transcript to a text file
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'path' - The file to write to
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.transcript())
+    }
+}
+
+// Puts heuristic scores and proven results on the same scale
+fn review_score(entry: &MoveAnalysis) -> i32 {
+    match entry.verdict {
+        Some(Verdict::Win(margin)) => margin * PROVEN_DISC_VALUE,
+        Some(Verdict::Loss(margin)) => -margin * PROVEN_DISC_VALUE,
+        Some(Verdict::Draw) => 0,
+        None => entry.score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_logic::EngineOptions;
+
+    // Black to move with ten empty squares. G1 wins by 18, B7 loses by 40
+    const BLACK: u64 = 0x80c0_c099_1b87_c4be;
+    const WHITE: u64 = 0x2c3c_3f66_e478_3800;
+    const G1: u64 = 1 << 6;
+    const B7: u64 = 1 << 49;
+
+    fn reviewed(player: constants::Color, loss: i32) -> ReviewedMove {
+        ReviewedMove { player, played_move: G1, best_move: G1, loss, annotation: Annotation::from_loss(loss) }
+    }
+
+    #[test]
+    fn flags_a_known_blunder_and_gives_the_best_move_no_loss() {
+        let mut position = Board::new();
+        position.black = BLACK;
+        position.white = WHITE;
+        let mut handler = AiLogicHandler::new(constants::Color::White, constants::Difficulty::Normal, EngineOptions::default(), 0);
+        let review = GameReview::analyze(&mut handler, &[(position.clone(), G1), (position, B7)], Duration::from_secs(5), 0);
+
+        assert_eq!(review.moves[0].loss, 0);
+        assert_eq!(review.moves[0].annotation, Annotation::Best);
+        // Turning a win by 18 into a loss by 40 costs more than the cap
+        assert_eq!(review.moves[1].best_move, G1);
+        assert_eq!(review.moves[1].loss, MAX_LOSS);
+        assert_eq!(review.moves[1].annotation, Annotation::Blunder);
+        assert!(review.transcript().contains("blunder ??"));
+    }
+
+    #[test]
+    fn labels_follow_the_loss_thresholds() {
+        assert_eq!(Annotation::from_loss(0), Annotation::Best);
+        assert_eq!(Annotation::from_loss(1), Annotation::Good);
+        assert_eq!(Annotation::from_loss(INACCURACY_THRESHOLD - 1), Annotation::Good);
+        assert_eq!(Annotation::from_loss(INACCURACY_THRESHOLD), Annotation::Inaccuracy);
+        assert_eq!(Annotation::from_loss(MISTAKE_THRESHOLD), Annotation::Mistake);
+        assert_eq!(Annotation::from_loss(BLUNDER_THRESHOLD - 1), Annotation::Mistake);
+        assert_eq!(Annotation::from_loss(BLUNDER_THRESHOLD), Annotation::Blunder);
+    }
+
+    #[test]
+    fn summarizes_accuracy_and_losses_per_player() {
+        let moves = vec![
+            reviewed(constants::Color::Black, 0),
+            reviewed(constants::Color::White, 120),
+            reviewed(constants::Color::Black, 39),
+            reviewed(constants::Color::Black, 40),
+            reviewed(constants::Color::Black, 300),
+        ];
+        let review = GameReview { moves, seed: 42 };
+        // Two of black's four moves lost less than an inaccuracy
+        assert_eq!(review.accuracy(constants::Color::Black), 50.0);
+        assert_eq!(review.average_loss(constants::Color::Black), 94.75);
+        assert_eq!(review.accuracy(constants::Color::White), 0.0);
+        assert_eq!(review.count(constants::Color::Black, Annotation::Inaccuracy), 1);
+        assert_eq!(review.count(constants::Color::Black, Annotation::Blunder), 1);
+        assert_eq!(review.count(constants::Color::White, Annotation::Mistake), 1);
+        // A player without moves made no mistakes
+        let empty = GameReview { moves: Vec::new(), seed: 0 };
+        assert_eq!(empty.accuracy(constants::Color::Black), 100.0);
+        assert_eq!(empty.average_loss(constants::Color::Black), 0.0);
+
+        let path = std::env::temp_dir().join(format!("rthello-review-{}.txt", std::process::id()));
+        review.export(path.to_str().unwrap()).unwrap();
+        let exported = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(exported, review.transcript());
+        assert!(exported.starts_with("Seed 42\n"));
+        assert!(exported.contains("Black: accuracy 50.0%, average loss 94.8, 1 inaccuracies, 0 mistakes, 1 blunders"));
+    }
+
+    #[test]
+    fn proven_results_count_by_the_disc() {
+        let entry = |score: i32, verdict: Option<Verdict>| MoveAnalysis { chosen_move: G1, score, principal_variation: vec![G1], verdict };
+        assert_eq!(review_score(&entry(37, None)), 37);
+        assert_eq!(review_score(&entry(37, Some(Verdict::Win(3)))), 3 * PROVEN_DISC_VALUE);
+        assert_eq!(review_score(&entry(37, Some(Verdict::Loss(2)))), -2 * PROVEN_DISC_VALUE);
+        assert_eq!(review_score(&entry(37, Some(Verdict::Draw))), 0);
+    }
+}
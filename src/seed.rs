@@ -0,0 +1,66 @@
+//Imports
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// The random number generator engines, games, tournaments and training draw from. The same
+/// seed always gives the same numbers, so a run can be replayed from its seed. The replay is only
+/// exact when every engine stops on a node or playout budget: how far a time-limited search gets
+/// depends on the machine and its load
+pub type GameRng = StdRng;
+
+///Returns a new seed for runs that were not given one with --seed
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+///Returns a generator started from the seed
+///
+///  # Arguments
+///
+///  * 'seed' - The seed to start from
+///
+///  # Examples
+///
+/// ```
+/// let mut rng = seed::rng(42);
+/// ```
+pub fn rng(seed: u64) -> GameRng {
+    StdRng::seed_from_u64(seed)
+}
+
+///Returns the seed of one numbered stream of a seed. Work spread over threads gives every game its
+/// own stream, so the numbers a game draws do not depend on which thread plays it
+///
+///  # Arguments
+///
+///  * 'seed' - The seed of the whole run
+///  * 'stream' - The number of the stream, for example the game number
+///
+///  # Examples
+///
+/// ```
+/// let mut rng = seed::rng(seed::split(seed, game as u64));
+/// ```
+pub fn split(seed: u64, stream: u64) -> u64 {
+    // SplitMix64 of the seed moved along by the stream number
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_replay_and_streams_differ() {
+        let draws = |seed: u64| -> Vec<u32> {
+            let mut rng = rng(seed);
+            (0..8).map(|_| rng.gen()).collect()
+        };
+        assert_eq!(draws(7), draws(7));
+        assert_ne!(draws(split(7, 0)), draws(split(7, 1)));
+        assert_eq!(split(7, 3), split(7, 3));
+    }
+}
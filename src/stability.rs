@@ -0,0 +1,192 @@
+//Imports
+use std::sync::OnceLock;
+
+//Modules
+use crate::constants;
+
+// Multiplying the a-file by this gathers one bit per rank into the top byte
+const GATHER_RANKS: u64 = 0x8040201008040201;
+const RANK_1: u64 = 0x00000000000000ff;
+
+// Lines running from a1 towards h8, and from h1 towards a8
+const DIAGONALS: [u64; 15] = diagonal_masks(false);
+const ANTI_DIAGONALS: [u64; 15] = diagonal_masks(true);
+// Turns an a-file pattern from extract_file back into a bitboard
+const FILE_A_DEPOSIT: [u64; 256] = file_a_deposit();
+
+// Stable discs of either color on an edge, indexed by the two 8 bit edge patterns
+static EDGE_TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+
+///Returns every disc of player that can never be flipped for the rest of the game.
+/// Edges are looked up in a table that is exact for corner-anchored and filled edges,
+/// discs on lines with no empty squares are added, and the set is then grown
+/// through the interior until no more discs can be proven stable.
+///
+///  # Arguments
+///
+///  * 'player' - The bitboard of the player to find stable discs for
+///  * 'opponent' - The bitboard of the other player
+///  * 'squares' - Every square of the board, smaller boards sit in the a1 corner
+///
+///  # Examples
+///
+/// ```
+/// let stable = stability::stable_discs(board.black, board.white, board.squares);
+/// ```
+pub fn stable_discs(player: u64, opponent: u64, squares: u64) -> u64 {
+    // Squares off the board can never be played, so they count as taken
+    let occupied = player | opponent | !squares;
+    let mut stable = edge_stable(player, opponent, (squares & constants::FILE_A).count_ones()) & player;
+
+    // A disc is safe along a line that has no empty squares left
+    let (full_rows, full_columns, full_diagonals, full_anti_diagonals) = full_lines(occupied);
+    stable |= player & full_rows & full_columns & full_diagonals & full_anti_diagonals;
+
+    // Squares with no neighbour on the board on one side of each line
+    let row_ends = squares & !(((squares << 1) & !constants::FILE_A) & ((squares >> 1) & !constants::FILE_H));
+    let column_ends = squares & !((squares << 8) & (squares >> 8));
+    let diagonal_ends = squares & !(((squares << 9) & !constants::FILE_A) & ((squares >> 9) & !constants::FILE_H));
+    let anti_diagonal_ends = squares & !(((squares << 7) & !constants::FILE_H) & ((squares >> 7) & !constants::FILE_A));
+
+    // A disc that has a stable neighbour of its own color, the edge of the board or a full line
+    // on every one of the 4 lines through it can never be flipped
+    loop {
+        let horizontal = full_rows | row_ends
+            | ((stable << 1) & !constants::FILE_A) | ((stable >> 1) & !constants::FILE_H);
+        let vertical = full_columns | column_ends | (stable << 8) | (stable >> 8);
+        let diagonal = full_diagonals | diagonal_ends
+            | ((stable << 9) & !constants::FILE_A) | ((stable >> 9) & !constants::FILE_H);
+        let anti_diagonal = full_anti_diagonals | anti_diagonal_ends
+            | ((stable << 7) & !constants::FILE_H) | ((stable >> 7) & !constants::FILE_A);
+        let grown = stable | (player & horizontal & vertical & diagonal & anti_diagonal);
+        if grown == stable {
+            return stable;
+        }
+        stable = grown;
+    }
+}
+
+// Stable discs of both colors on the 4 edges of a board with size rows. The squares of the edge
+// patterns that are off a smaller board look empty to the table, which only costs stable discs
+fn edge_stable(player: u64, opponent: u64, size: u32) -> u64 {
+    let table = EDGE_TABLE.get_or_init(build_edge_table);
+    let lookup = |player_edge: u64, opponent_edge: u64| table[((player_edge as usize) << 8) | opponent_edge as usize] as u64;
+    let last = size - 1;
+
+    let bottom = lookup(player & RANK_1, opponent & RANK_1);
+    let top = lookup((player >> (8 * last)) & RANK_1, (opponent >> (8 * last)) & RANK_1) << (8 * last);
+    let left = FILE_A_DEPOSIT[lookup(extract_file(player, 0), extract_file(opponent, 0)) as usize];
+    let right = FILE_A_DEPOSIT[lookup(extract_file(player, last), extract_file(opponent, last)) as usize] << last;
+    bottom | top | left | right
+}
+
+// Packs one column into the low 8 bits
+fn extract_file(bits: u64, file: u32) -> u64 {
+    (((bits >> file) & constants::FILE_A).wrapping_mul(GATHER_RANKS)) >> 56
+}
+
+// Masks of every row, column and diagonal with no empty squares
+fn full_lines(occupied: u64) -> (u64, u64, u64, u64) {
+    let mut rows: u64 = 0;
+    let mut columns: u64 = 0;
+    for index in 0..8 {
+        let row = RANK_1 << (8 * index);
+        if occupied & row == row {
+            rows |= row;
+        }
+        let column = constants::FILE_A << index;
+        if occupied & column == column {
+            columns |= column;
+        }
+    }
+    let full = |lines: &[u64; 15]| lines.iter().filter(|&&line| occupied & line == line).fold(0, |mask, line| mask | line);
+    (rows, columns, full(&DIAGONALS), full(&ANTI_DIAGONALS))
+}
+
+const fn diagonal_masks(anti: bool) -> [u64; 15] {
+    let mut masks = [0u64; 15];
+    let mut square: usize = 0;
+    while square < 64 {
+        let rank = (square / 8) as i32;
+        let file = (square % 8) as i32;
+        let index = if anti { rank + file } else { rank - file + 7 };
+        masks[index as usize] |= 1 << square;
+        square += 1;
+    }
+    masks
+}
+
+const fn file_a_deposit() -> [u64; 256] {
+    let mut deposit = [0u64; 256];
+    let mut pattern = 0;
+    while pattern < 256 {
+        let mut rank = 0;
+        while rank < 8 {
+            // extract_file puts rank r in bit 7 - r
+            if pattern & (1 << (7 - rank)) != 0 {
+                deposit[pattern] |= 1 << (8 * rank);
+            }
+            rank += 1;
+        }
+        pattern += 1;
+    }
+    deposit
+}
+
+// Edge discs can only be flipped along the edge itself, so an edge can be solved on its own.
+// A disc is stable if it keeps its color after any sequence of discs of either color is placed
+// on the empty squares, whether or not the placement flips anything along the edge.
+fn build_edge_table() -> Vec<u8> {
+    let mut table: Vec<u8> = vec![0; 256 * 256];
+    // Fewer empty squares first, so every position a placement leads to is already solved
+    for empties in 0..=8u32 {
+        for player in 0..256usize {
+            for opponent in 0..256usize {
+                if player & opponent != 0 || (!(player | opponent) & 0xff).count_ones() != empties {
+                    continue;
+                }
+                let mut stable = (player | opponent) as u8;
+                for square in 0..8 {
+                    if (player | opponent) & (1 << square) != 0 {
+                        continue;
+                    }
+                    for player_moves in [true, false] {
+                        let (mover, other) = match player_moves {
+                            true => (player, opponent),
+                            false => (opponent, player),
+                        };
+                        let flips = edge_flips(mover, other, square);
+                        let new_mover = mover | (1 << square) | flips;
+                        let new_other = other & !flips;
+                        let (new_player, new_opponent) = match player_moves {
+                            true => (new_mover, new_other),
+                            false => (new_other, new_mover),
+                        };
+                        // Discs that keep their color and stay stable afterwards
+                        let unchanged = (player & new_player) | (opponent & new_opponent);
+                        stable &= table[(new_player << 8) | new_opponent] & unchanged as u8;
+                    }
+                }
+                table[(player << 8) | opponent] = stable;
+            }
+        }
+    }
+    table
+}
+
+// Opponent runs on the edge closed off by mover after playing on square
+fn edge_flips(mover: usize, other: usize, square: usize) -> usize {
+    let mut flipped: usize = 0;
+    for step in [-1i32, 1] {
+        let mut run: usize = 0;
+        let mut index = square as i32 + step;
+        while (0..8).contains(&index) && other & (1 << index) != 0 {
+            run |= 1 << index;
+            index += step;
+        }
+        if (0..8).contains(&index) && mover & (1 << index) != 0 {
+            flipped |= run;
+        }
+    }
+    flipped
+}
@@ -0,0 +1,326 @@
+//Imports
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use rand::Rng;
+
+//Modules
+use crate::board;
+use crate::constants;
+use crate::seed::{self, GameRng};
+use crate::ai_logic::{random_ai, strength, AiType, EngineOptions};
+use crate::ai_logic::transposition::TranspositionTable;
+use crate::ai_logic::monte_carlo_tree_search::{MctsSettings, RaveSchedule, Tree};
+
+// Random moves played before the engines take over, so the games of a match are not all the same
+const OPENING_PLIES: usize = 6;
+// The RAVE schedule the benchmark uses when the command line did not pick one
+const BENCHMARK_RAVE: RaveSchedule = RaveSchedule::Equivalence(1000.0);
+// Entries of the transposition table each side keeps on every worker. Tournament moves are short
+// and every core plays at once, so the tables are smaller than the one a game against the user keeps
+const TOURNAMENT_TABLE_SIZE: usize = 1 << 16;
+
+// Picks a move for the player to move with the entrant's transposition table and the random number generator of the game
+type MoveChooser<'a> = Box<dyn Fn(&board::Board, &mut TranspositionTable, &mut GameRng) -> u64 + Sync + 'a>;
+
+/// A player in a tournament, with the function that picks its moves
+pub struct Entrant<'a> {
+    pub name: String,
+    choose_move: MoveChooser<'a>,
+}
+
+impl<'a> Entrant<'a> {
+    ///Creates an entrant that plays the moves the function picks
+    ///
+    ///  # Arguments
+    ///
+    ///  * 'name' - The name printed in the results
+    ///  * 'choose_move' - Picks a move for the player to move with the entrant's own transposition table
+    ///    and the game's random number generator, it is only called when there is one
+    ///
+    ///  # Examples
+    ///
+    /// ```
+    /// let random = Entrant::new("random", |board, _, rng| random_ai::get_random_move(board.get_possible_moves(), rng));
+    /// ```
+    pub fn new(name: &str, choose_move: impl Fn(&board::Board, &mut TranspositionTable, &mut GameRng) -> u64 + Sync + 'a) -> Self {
+        Self { name: name.to_string(), choose_move: Box::new(choose_move) }
+    }
+}
+
+/// The results of a match from the point of view of the first entrant
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MatchResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub disc_margin: i64
+}
+
+impl MatchResult {
+    ///Returns the number of games played
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    ///Returns the share of the points the first entrant scored, counting a draw as half a point
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    ///Returns the Elo difference the score corresponds to, with the 95% error margin of the estimate
+    pub fn elo_difference(&self) -> (f64, f64) {
+        let games = self.games().max(1) as f64;
+        // Keep the score off 0 and 1, where the difference is unbounded
+        let score = self.score().clamp(0.5 / games, 1.0 - 0.5 / games);
+        let elo = |score: f64| -400.0 * (1.0 / score - 1.0).log10();
+        let points = [(1.0, self.wins), (0.5, self.draws), (0.0, self.losses)];
+        let variance = points.iter().map(|&(value, count)| count as f64 * (value - score).powi(2)).sum::<f64>() / games;
+        let error = 1.96 * (variance / games).sqrt();
+        (elo(score), (elo((score + error).min(0.999)) - elo((score - error).max(0.001))) / 2.0)
+    }
+}
+
+///Plays a match between two entrants and returns the results of the first. Games come in pairs
+/// that start from the same random opening with the colors swapped, so neither entrant is favored
+/// by the openings. Games are played on every core. Every game draws from its own stream of the
+/// seed, so the openings and random choices do not depend on which thread plays the game. Each
+/// worker keeps a transposition table per entrant and reuses it for all of its games.
+///
+///  # Arguments
+///
+///  * 'first' - The entrant the results are counted for
+///  * 'second' - Its opponent
+///  * 'games' - How many games to play, rounded up to an even number
+///  * 'variant' - The rules the games are played by
+///  * 'seed' - The seed of the match
+///
+///  # Examples
+///
+/// ```
+/// let result = tournament::play_match(&rave, &uct, 100, Variant::Standard, seed);
+/// println!("{:.1}%", 100.0 * result.score());
+/// ```
+pub fn play_match(first: &Entrant, second: &Entrant, games: usize, variant: constants::Variant, seed: u64) -> MatchResult {
+    let pairs = games.div_ceil(2);
+    let next_pair = AtomicUsize::new(0);
+    let result = Mutex::new(MatchResult::default());
+    thread::scope(|scope| {
+        for _ in 0..workers() {
+            scope.spawn(|| {
+                let mut tables = [TranspositionTable::with_size(TOURNAMENT_TABLE_SIZE), TranspositionTable::with_size(TOURNAMENT_TABLE_SIZE)];
+                loop {
+                    let pair = next_pair.fetch_add(1, Ordering::Relaxed);
+                    if pair >= pairs {
+                        break;
+                    }
+                    let pair_seed = seed::split(seed, pair as u64);
+                    let opening = random_opening(&mut seed::rng(pair_seed)).with_variant(variant);
+                    for (game, first_color) in [constants::Color::Black, constants::Color::White].into_iter().enumerate() {
+                        let mut rng = seed::rng(seed::split(pair_seed, game as u64 + 1));
+                        let margin = play_game(opening.clone(), [first, second], &mut tables, first_color, &mut rng);
+                        if let Ok(mut result) = result.lock() {
+                            match margin {
+                                m if m > 0 => result.wins += 1,
+                                m if m < 0 => result.losses += 1,
+                                _ => result.draws += 1,
+                            }
+                            result.disc_margin += margin as i64;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    result.into_inner().unwrap_or_default()
+}
+
+///Prints the results of a match with the score and Elo difference of the first entrant
+///
+///  # Arguments
+///
+///  * 'first' - The entrant the results are for
+///  * 'second' - Its opponent
+///  * 'result' - The results of the match
+pub fn print_result(first: &Entrant, second: &Entrant, result: &MatchResult) {
+    let (elo, error) = result.elo_difference();
+    println!("{} against {}: +{} ={} -{} in {} games", first.name, second.name, result.wins, result.draws, result.losses, result.games());
+    println!("score {:.1}%, average disc margin {:+.1}, Elo difference {:+.0} +/- {:.0}",
+        100.0 * result.score(), result.disc_margin as f64 / result.games().max(1) as f64, elo, error);
+}
+
+///Plays tree search with RAVE against the same search without it at an equal number of playouts
+/// per move, and prints the results
+///
+///  # Arguments
+///
+///  * 'settings' - The tree search settings both sides use, the RAVE schedule is taken from them if one is set
+///  * 'games' - How many games to play
+///  * 'playouts' - How many playouts each side runs per move
+///  * 'seed' - The seed of the match
+///
+///  # Examples
+///
+/// ```
+/// tournament::run_rave_benchmark(MctsSettings::default(), 100, 2000, seed);
+/// ```
+pub fn run_rave_benchmark(settings: MctsSettings, games: usize, playouts: usize, seed: u64) {
+    let rave = MctsSettings { rave: Some(settings.rave.unwrap_or(BENCHMARK_RAVE)), ..settings };
+    let uct = MctsSettings { rave: None, ..settings };
+    let tree_search = |settings: MctsSettings| move |board: &board::Board, _: &mut TranspositionTable, rng: &mut GameRng| {
+        let mut tree = Tree::new(board.clone(), settings, None, rng.gen());
+        tree.run(playouts);
+        tree.best_move()
+    };
+    let first = Entrant::new("RAVE", tree_search(rave));
+    let second = Entrant::new("UCT", tree_search(uct));
+    println!("{:?} against plain UCT at {} playouts per move, {} games, seed {}", rave.rave.unwrap_or(BENCHMARK_RAVE), playouts, games, seed);
+    let result = play_match(&first, &second, games, constants::Variant::Standard, seed);
+    print_result(&first, &second, &result);
+}
+
+///Returns an entrant that plays the moves of an engine from the registry
+///
+///  # Arguments
+///
+///  * 'engine' - The engine to play with
+///  * 'options' - The engine settings
+///  * 'move_time' - How long the engine may think per move
+pub fn engine_entrant<'a>(engine: AiType, options: EngineOptions, move_time: Duration) -> Entrant<'a> {
+    let name = engine.to_string();
+    Entrant::new(&name, move |board: &board::Board, table: &mut TranspositionTable, rng: &mut GameRng| engine.choose_move(board, move_time, &options, table, rng))
+}
+
+///Plays a match between two engines from the registry and prints the results of the first
+///
+///  # Arguments
+///
+///  * 'first' - The engine the results are for
+///  * 'second' - Its opponent
+///  * 'options' - The engine settings both sides use
+///  * 'games' - How many games to play
+///  * 'move_time' - How long each engine may think per move
+///  * 'variant' - The rules the games are played by
+///  * 'seed' - The seed of the match
+///
+///  # Examples
+///
+/// ```
+/// tournament::run_engine_match(AiType::Bot(Bot::Greedy), AiType::Random, EngineOptions::default(), 100, Duration::from_millis(100), Variant::Anti, seed);
+/// ```
+pub fn run_engine_match(first: AiType, second: AiType, options: EngineOptions, games: usize, move_time: Duration, variant: constants::Variant, seed: u64) {
+    let first = engine_entrant(first, options, move_time);
+    let second = engine_entrant(second, options, move_time);
+    println!("{} against {}, {} games of {} Othello, seed {}", first.name, second.name, games, variant, seed);
+    let result = play_match(&first, &second, games, variant, seed);
+    print_result(&first, &second, &result);
+}
+
+///Measures the Elo of every strength level. Level 1 plays the random player and every other level
+/// plays the level below it, and the differences are added up into a rating ladder that starts at
+/// 0 for the random player. The error margins of the matches add up along the ladder.
+///
+///  # Arguments
+///
+///  * 'options' - The engine settings every level plays with
+///  * 'games' - How many games each match plays
+///  * 'move_time' - The most time a level may think per move, the stronger levels are only limited by it
+///  * 'seed' - The seed of the ladder, every match gets its own stream of it
+///
+///  # Examples
+///
+/// ```
+/// tournament::run_level_ladder(EngineOptions::default(), 40, Duration::from_millis(200), seed);
+/// ```
+pub fn run_level_ladder(options: EngineOptions, games: usize, move_time: Duration, seed: u64) {
+    let random = Entrant::new("random", |board: &board::Board, _: &mut TranspositionTable, rng: &mut GameRng| random_ai::get_random_move(board.get_possible_moves(), rng));
+    let entrants: Vec<Entrant> = strength::LEVELS.iter().enumerate().map(|(index, &level)| {
+        Entrant::new(&format!("level {}", index + 1), move |board: &board::Board, table: &mut TranspositionTable, rng: &mut GameRng| {
+            strength::get_level_move(board, level, move_time, &options, table, rng)
+        })
+    }).collect();
+    println!("Rating every level against the one below it, {} games per match, up to {}ms per move, seed {}", games, move_time.as_millis(), seed);
+    println!("Level	Depth	Nodes		Noise	Blunder	Score	Elo");
+    let (mut elo, mut variance) = (0.0, 0.0);
+    for (index, entrant) in entrants.iter().enumerate() {
+        let opponent = match index {
+            0 => &random,
+            _ => &entrants[index - 1],
+        };
+        let result = play_match(entrant, opponent, games, constants::Variant::Standard, seed::split(seed, index as u64));
+        let (difference, error) = result.elo_difference();
+        elo += difference;
+        variance += error * error;
+        let level = strength::LEVELS[index];
+        let nodes = match level.nodes {
+            u64::MAX => String::from("-"),
+            nodes => nodes.to_string(),
+        };
+        println!("{}	{}	{:<8}	{}	{:.1}%	{:.1}%	{:+.0} +/- {:.0}",
+            index + 1, level.depth, nodes, level.noise, 100.0 * level.blunder, 100.0 * result.score(), elo, variance.sqrt());
+    }
+}
+
+// Plays a game from the position and returns the final disc margin of the first entrant, positive when it won.
+// Each entrant searches with the table of the same index
+fn play_game(mut board: board::Board, entrants: [&Entrant; 2], tables: &mut [TranspositionTable; 2], first_color: constants::Color, rng: &mut GameRng) -> i32 {
+    while !board::Board::check_game_over(&board) {
+        let possible_moves = board.get_possible_moves();
+        if possible_moves == 0 {
+            board.pass_turn();
+            continue;
+        }
+        let side = match board.current_player == first_color {
+            true => 0,
+            false => 1,
+        };
+        let selected_move = (entrants[side].choose_move)(&board, &mut tables[side], rng);
+        board.place_move(&possible_moves, selected_move);
+    }
+    board.disc_margin(first_color)
+}
+
+// The starting position after a few random moves, with the player to move still able to move
+fn random_opening<R: Rng>(rng: &mut R) -> board::Board {
+    loop {
+        let mut board = board::Board::new();
+        for _ in 0..OPENING_PLIES {
+            let possible_moves = board.get_possible_moves();
+            if possible_moves == 0 {
+                break;
+            }
+            let mut selected_move = possible_moves;
+            for _ in 0..rng.gen_range(0..possible_moves.count_ones()) {
+                selected_move &= selected_move - 1;
+            }
+            board.place_move(&possible_moves, selected_move & selected_move.wrapping_neg());
+        }
+        if board.get_possible_moves() != 0 {
+            return board;
+        }
+    }
+}
+
+fn workers() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_logic::bots::Bot;
+
+    #[test]
+    fn matches_replay_from_their_seed() {
+        let options = EngineOptions::default();
+        let first = engine_entrant(AiType::EpsilonGreedy(0.3, Box::new(AiType::Bot(Bot::Greedy))), options, Duration::ZERO);
+        let second = engine_entrant(AiType::Random, options, Duration::ZERO);
+        let result = |seed: u64| {
+            let result = play_match(&first, &second, 16, constants::Variant::Standard, seed);
+            (result.wins, result.draws, result.losses, result.disc_margin)
+        };
+        assert_eq!(result(11), result(11));
+        assert_ne!(result(11), result(12));
+    }
+}